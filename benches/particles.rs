@@ -0,0 +1,121 @@
+//! Compares stepping particles with `time_step_with_rk4` against the
+//! allocation-free structure-of-arrays path, serial and parallel.
+//!
+//! Run with `cargo bench --bench particles`.
+
+use agentbox::physics::{
+    self, par_accelerations, Collider, Particle, ParticleSet, ParticleView, Plane, Rk4Scratch,
+    Sphere,
+};
+use cgmath::{prelude::*, Vector3};
+use criterion::black_box;
+use std::time::{Duration, Instant};
+
+const GRAVITY: Vector3<f32> = Vector3::new(0.0, 0.0, -9.81);
+// How long to keep stepping each configuration
+const BENCH_TIME: Duration = Duration::from_millis(500);
+// Steps taken from the initial state in every timed run
+const STEPS_PER_RUN: u32 = 100;
+
+fn main() {
+    let obstacles = [
+        Sphere::new(Vector3::new(0.0, 0.0, 1.0), 1.0),
+        Sphere::new(Vector3::new(3.0, 2.0, 1.0), 0.5),
+        Sphere::new(Vector3::new(-2.0, 3.0, 0.5), 1.5),
+    ];
+    // Some work per particle, so that there is something to parallelize
+    let accel = |particle: &Particle| {
+        let from_obstacles: Vector3<f32> = obstacles
+            .iter()
+            .map(|obstacle| obstacle.collide_with(particle))
+            .sum();
+        from_obstacles + Plane::FLOOR.collide_with(particle) + GRAVITY
+    };
+
+    println!(
+        "{:>8} {:>14} {:>14} {:>14}",
+        "count", "vec", "soa", "soa parallel"
+    );
+    for count in [10, 100, 1_000, 10_000, 100_000] {
+        let particles = initial_particles(count);
+
+        let vec = bench(|| {
+            let mut particles = particles.clone();
+            move || {
+                particles = physics::time_step_with_rk4(&particles, &(), |particles, ()| {
+                    particles.iter().map(accel).collect()
+                });
+                black_box(&particles);
+            }
+        });
+
+        let soa = bench(|| {
+            let mut set = ParticleSet::from_particles(&particles);
+            let mut scratch = Rk4Scratch::new();
+            move || {
+                physics::step_with_rk4(&mut set, &mut scratch, &(), |view, (), accels| {
+                    for (out, particle) in accels.iter_mut().zip(view.iter()) {
+                        *out = accel(&particle);
+                    }
+                });
+                black_box(&set);
+            }
+        });
+
+        let soa_parallel = bench(|| {
+            let mut set = ParticleSet::from_particles(&particles);
+            let mut scratch = Rk4Scratch::new();
+            move || {
+                physics::step_with_rk4(&mut set, &mut scratch, &(), |view, (), accels| {
+                    par_accelerations(view, accels, |view: ParticleView<'_>, i| {
+                        accel(&view.get(i))
+                    });
+                });
+                black_box(&set);
+            }
+        });
+
+        println!(
+            "{:>8} {:>14} {:>14} {:>14}",
+            count,
+            format_per_step(vec),
+            format_per_step(soa),
+            format_per_step(soa_parallel),
+        );
+    }
+}
+
+// A grid of particles falling onto the obstacles
+fn initial_particles(count: usize) -> Vec<Particle> {
+    let side = (count as f64).cbrt().ceil() as usize;
+    (0..count)
+        .map(|i| {
+            let (x, y, z) = (i % side, i / side % side, i / side / side);
+            let pos = Vector3::new(x as f32, y as f32, z as f32) * 0.25 + Vector3::unit_z() * 2.0;
+            Particle::new(pos, Vector3::zero(), 0.1)
+        })
+        .collect()
+}
+
+// The mean time per call of steppers made by `setup`. Every stepper starts
+// from the same state and takes the same number of steps, so that all
+// variants simulate the same thing.
+fn bench<F: FnMut()>(mut setup: impl FnMut() -> F) -> Duration {
+    let (mut total, mut steps) = (Duration::ZERO, 0);
+    while total < BENCH_TIME {
+        let mut step = setup();
+        // Warm up, growing any buffers
+        step();
+        let start = Instant::now();
+        for _ in 0..STEPS_PER_RUN {
+            step();
+        }
+        total += start.elapsed();
+        steps += STEPS_PER_RUN;
+    }
+    total / steps
+}
+
+fn format_per_step(duration: Duration) -> String {
+    format!("{:.1?}", duration)
+}
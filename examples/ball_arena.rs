@@ -0,0 +1,33 @@
+use agentbox::{models::BallArena, Status};
+use cgmath::prelude::*;
+use std::{thread, time::Duration};
+
+type Arena = BallArena<20>;
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<Arena, _>(Status::VISUAL, move |world, signals, _status| {
+        // Roll the nearest ball still outside the goal towards its center
+        let outside = world
+            .balls
+            .iter()
+            .enumerate()
+            .filter(|(_, ball)| !world.is_in_goal(ball));
+        let nearest = outside.min_by(|(_, a), (_, b)| {
+            let a = a.pos.truncate().distance(world.goal);
+            a.total_cmp(&b.pos.truncate().distance(world.goal))
+        });
+        if let Some((i, ball)) = nearest {
+            let direction = (world.goal - ball.pos.truncate()).normalize();
+            signals.ball = i;
+            signals.impulse = (direction - ball.vel.truncate()) * 0.3;
+        }
+
+        if world.episode.is_over() {
+            log::info!("Episode ended with reward {}", world.episode.total_reward);
+        }
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
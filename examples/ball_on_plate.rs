@@ -0,0 +1,21 @@
+use agentbox::{models::BallOnPlate, Status};
+use std::{thread, time::Duration};
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<BallOnPlate, _>(Status::VISUAL, move |world, signals, _status| {
+        // Tilt the plate to pull the ball towards the target, damping its speed
+        let error = world.ball_pos - world.target;
+        let push = error * 1.0 + world.ball_vel * 0.6;
+        // Pitching rolls the ball towards +x, and rolling towards -y
+        signals.pitch = -push.x;
+        signals.roll = push.y;
+
+        if world.episode.is_over() {
+            log::info!("Episode ended with reward {}", world.episode.total_reward);
+        }
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
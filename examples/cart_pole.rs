@@ -0,0 +1,18 @@
+use agentbox::{models::CartPole, Status};
+use std::{thread, time::Duration};
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<CartPole, _>(Status::VISUAL, move |world, signals, _status| {
+        // Push the cart under the pole, and gently back towards the middle
+        let lean = world.theta + 0.2 * world.theta_vel + 0.02 * world.x + 0.05 * world.x_vel;
+        signals.push(lean > 0.0);
+
+        if world.episode.is_over() {
+            log::info!("Episode ended with reward {}", world.episode.total_reward);
+        }
+
+        thread::sleep(Duration::from_secs_f32(CartPole::TAU));
+    })
+}
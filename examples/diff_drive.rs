@@ -0,0 +1,33 @@
+use agentbox::{models::DiffDrive, Status};
+use cgmath::{prelude::*, Vector2};
+use std::{f32::consts::PI, thread, time::Duration};
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<DiffDrive, _>(Status::VISUAL, move |world, signals, _status| {
+        // Head for the goal, pushed away from whatever the lidar sees nearby
+        let mut direction = (world.goal - world.pos).normalize();
+        for (i, range) in world.lidar_scan().into_iter().enumerate() {
+            if range < 1.0 {
+                let angle = world.heading + DiffDrive::lidar_angle(i);
+                let (sin, cos) = angle.sin_cos();
+                direction -= Vector2::new(cos, sin) * (0.3 * (1.0 / range - 1.0));
+            }
+        }
+
+        // Turn towards that direction, slowing down while facing away from it
+        let forward = world.forward();
+        let error = forward.perp_dot(direction).atan2(forward.dot(direction));
+        let speed = DiffDrive::MAX_WHEEL_SPEED * (1.0 - error.abs() / PI * 2.0).max(0.0) * 0.6;
+        let turn = error.clamp(-1.0, 1.0) * DiffDrive::MAX_WHEEL_SPEED * 0.4;
+        signals.left_wheel_speed = speed - turn;
+        signals.right_wheel_speed = speed + turn;
+
+        if world.episode.is_over() {
+            log::info!("Episode ended with reward {}", world.episode.total_reward);
+        }
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
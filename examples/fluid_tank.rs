@@ -0,0 +1,19 @@
+use agentbox::{models::FluidTank, Status};
+use cgmath::Vector2;
+use std::{thread, time::Duration};
+
+// About the frequency at which the settled water sloshes from end to end
+const SLOSH_RATE: f32 = 1.3;
+const SETTLE_TIME: f32 = 5.0;
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<FluidTank, _>(Status::VISUAL, move |world, signals, _status| {
+        // Let the dam break settle, then rock the tank to make waves
+        let rocking = (world.time - SETTLE_TIME).max(0.0);
+        signals.tank_accel = Vector2::unit_x() * (0.5 * (rocking * SLOSH_RATE).sin());
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
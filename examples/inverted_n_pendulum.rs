@@ -0,0 +1,20 @@
+use agentbox::{models::InvertedSinglePendulum, Status};
+use std::{thread, time::Duration};
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<InvertedSinglePendulum, _>(
+        Status::VISUAL,
+        move |world, signals, _status| {
+            // Chase the lean of the rod, and drift back to the origin by
+            // leaning towards it first
+            let lean = world.top_pos().truncate() - world.base_pos;
+            let lean_vel = world.node_vel[0].truncate() - world.base_vel;
+            signals.base_accel =
+                lean * 3.0 + lean_vel * 2.0 + world.base_pos * 0.1 + world.base_vel * 0.3;
+
+            thread::sleep(Duration::from_secs_f32(0.01));
+        },
+    )
+}
@@ -0,0 +1,39 @@
+use agentbox::{models::LunarLander, Status};
+use cgmath::{prelude::*, Vector3};
+use std::{thread, time::Duration};
+
+const MOON_GRAVITY: f32 = 1.62;
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<LunarLander, _>(Status::VISUAL, move |world, signals, _status| {
+        let body = &world.body;
+        let height = body.pos.z - LunarLander::PAD_HEIGHT;
+
+        // Drift over the pad, then descend more slowly the lower it gets
+        let mut accel = (-body.pos.truncate() * 0.2 - body.vel.truncate() * 0.8).extend(0.0);
+        if accel.magnitude() > 0.5 {
+            accel = accel.normalize() * 0.5;
+        }
+        let descent_speed = (height * 0.15).clamp(0.5, 3.0);
+        accel.z = (-descent_speed - body.vel.z) * 1.5 + MOON_GRAVITY;
+
+        // Turn the lander's axis along the acceleration
+        let up = body.orientation * Vector3::unit_z();
+        let torque = up.cross(accel.normalize()) * 40.0 - body.angular_vel * 30.0;
+        signals.attitude =
+            (body.orientation.conjugate() * torque) / LunarLander::MAX_ATTITUDE_TORQUE;
+        signals.main_throttle = body.mass * accel.dot(up).max(0.0) / LunarLander::MAX_MAIN_THRUST;
+
+        if world.episode.is_over() {
+            log::info!(
+                "Episode ended with reward {}, {}",
+                world.episode.total_reward,
+                if world.landed { "landed" } else { "crashed" },
+            );
+        }
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
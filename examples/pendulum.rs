@@ -0,0 +1,24 @@
+use agentbox::{models::Pendulum, Status};
+use std::{f32::consts::PI, thread, time::Duration};
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<Pendulum, _>(Status::VISUAL, move |world, signals, _status| {
+        let angle = (world.theta + PI).rem_euclid(2.0 * PI) - PI;
+        // Proportional to the mechanical energy, and 5 when upright at rest
+        let energy = world.theta_vel * world.theta_vel / 6.0 + 5.0 * angle.cos();
+
+        signals.torque = if angle.abs() < 0.6 {
+            // Balance
+            -20.0 * angle - 5.0 * world.theta_vel
+        } else if energy < 5.0 {
+            // Pump energy in by pushing along the swing
+            Pendulum::MAX_TORQUE * world.theta_vel.signum()
+        } else {
+            0.0
+        };
+
+        thread::sleep(Duration::from_secs_f32(Pendulum::DT));
+    })
+}
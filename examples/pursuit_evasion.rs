@@ -0,0 +1,71 @@
+use agentbox::{
+    models::{PursuitEvasion, Role, Sighting},
+    multi_agent::{self, AgentController, MultiAgentModel},
+    Status,
+};
+use cgmath::{prelude::*, Vector2};
+use std::{thread, time::Duration};
+
+type Game = PursuitEvasion;
+
+// Head for where the nearest evader will be by the time we get there
+fn pursue(evaders: &[Sighting]) -> Vector2<f32> {
+    let nearest = evaders
+        .iter()
+        .min_by(|a, b| a.offset.magnitude().total_cmp(&b.offset.magnitude()));
+    match nearest {
+        Some(evader) => {
+            let lead = evader.offset.magnitude() / Role::Pursuer.max_speed();
+            evader.offset + evader.vel * lead
+        }
+        None => Vector2::zero(),
+    }
+}
+
+// Run away from the pursuers, the nearest most of all, and from the walls
+fn evade(pos: Vector2<f32>, pursuers: &[Sighting]) -> Vector2<f32> {
+    let mut away: Vector2<f32> = pursuers
+        .iter()
+        .map(|pursuer| -pursuer.offset / pursuer.offset.magnitude2())
+        .sum();
+    let half_size = Game::ARENA_SIZE / 2.0;
+    for axis in 0..2 {
+        away[axis] += 1.0 / (half_size + pos[axis]) - 1.0 / (half_size - pos[axis]);
+    }
+    away
+}
+
+fn main() {
+    env_logger::init();
+
+    let controllers = (0..Game::agent_count())
+        .map(|_| -> AgentController<Game> {
+            Box::new(|observation, _reward, accel| {
+                let direction = match observation.role {
+                    Role::Pursuer => pursue(&observation.evaders),
+                    Role::Evader => evade(observation.pos, &observation.pursuers),
+                };
+                let mut target_vel = Vector2::zero();
+                if direction != Vector2::zero() {
+                    target_vel = direction.normalize_to(observation.role.max_speed());
+                }
+                *accel = (target_vel - observation.vel) * 5.0;
+            })
+        })
+        .collect();
+    let mut agents = multi_agent::per_agent::<Game>(controllers);
+
+    agentbox::run_with::<Game, _>(Status::VISUAL, move |world, signals, status| {
+        agents(world, signals, status);
+
+        if world.episode.is_over() {
+            log::info!(
+                "Episode ended after {} steps with pursuer reward {}",
+                world.episode.steps,
+                world.episode.total_reward,
+            );
+        }
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
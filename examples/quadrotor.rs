@@ -0,0 +1,50 @@
+use agentbox::{
+    models::{Quadrotor, QuadrotorWaypoints},
+    Status,
+};
+use cgmath::{prelude::*, Vector3};
+use std::{thread, time::Duration};
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<QuadrotorWaypoints, _>(Status::VISUAL, move |world, signals, _status| {
+        let body = &world.body;
+        let target = world.target().unwrap_or(body.pos);
+
+        // Accelerate towards the target, limiting how far to tilt
+        let mut accel = (target - body.pos) * 4.0 - body.vel * 3.0;
+        let horizontal = accel.truncate();
+        if horizontal.magnitude() > 5.0 {
+            accel = (horizontal.normalize() * 5.0).extend(accel.z);
+        }
+        accel += Vector3::unit_z() * 9.81;
+
+        // Turn the body's z axis along the acceleration, and hold the heading
+        let up = body.orientation * Vector3::unit_z();
+        let forward = body.orientation * Vector3::unit_x();
+        let torque = up.cross(accel.normalize()) * 1.0 + Vector3::unit_z() * (-forward.y * 0.1)
+            - body.angular_vel * 0.2;
+        let torque = body.orientation.conjugate() * torque;
+        let thrust = Quadrotor::MASS * accel.dot(up).max(0.0);
+
+        // Share out the thrust and torque between the motors
+        let (roll, pitch) = (
+            torque.x / (2.0 * Quadrotor::ARM_LENGTH),
+            torque.y / (2.0 * Quadrotor::ARM_LENGTH),
+        );
+        let yaw = torque.z / (4.0 * Quadrotor::DRAG_COEFFICIENT);
+        signals.motor_thrusts = [
+            thrust / 4.0 - pitch - yaw,
+            thrust / 4.0 + roll + yaw,
+            thrust / 4.0 + pitch - yaw,
+            thrust / 4.0 - roll + yaw,
+        ];
+
+        if world.episode.is_over() {
+            log::info!("Episode ended with reward {}", world.episode.total_reward);
+        }
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
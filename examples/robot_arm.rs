@@ -0,0 +1,36 @@
+use agentbox::{models::ThreeLinkArm, Status};
+use cgmath::{prelude::*, Vector3};
+use std::{thread, time::Duration};
+
+const GRAVITY: f32 = 9.81;
+
+fn main() {
+    env_logger::init();
+
+    agentbox::run_with::<ThreeLinkArm, _>(Status::VISUAL, move |world, signals, _status| {
+        let joints = world.joint_positions();
+        let effector = world.end_effector();
+        let max_torques = ThreeLinkArm::max_torques();
+        let axis = -Vector3::unit_y();
+
+        // Pull the end effector towards the target through the transposed
+        // Jacobian, and hold up the links beyond each joint against gravity
+        let pull = (world.target - effector) * 60.0;
+        let weight = Vector3::new(0.0, 0.0, ThreeLinkArm::LINK_MASS * GRAVITY);
+        for (i, pivot) in joints.iter().enumerate() {
+            let mut torque = axis.cross(effector - pivot).dot(pull);
+            for (k, start) in joints.iter().enumerate().skip(i) {
+                let end = joints.get(k + 1).unwrap_or(&effector);
+                let center = (start + end) / 2.0;
+                torque += axis.cross(center - pivot).dot(weight);
+            }
+            signals.torques[i] = torque - world.velocities[i] * 0.3 * max_torques[i];
+        }
+
+        if world.episode.is_over() {
+            log::info!("Episode ended with reward {}", world.episode.total_reward);
+        }
+
+        thread::sleep(Duration::from_secs_f32(0.01));
+    })
+}
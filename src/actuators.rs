@@ -0,0 +1,189 @@
+//! Imperfect actuation, for controllers that should cope with limited
+//! authority.
+//!
+//! An [`Actuator`] sits between a signal and what the model applies. Models
+//! keep their actuators in the world, so controllers can read back the applied
+//! value with [`Actuator::output`]. Signals may be vectors of any dimension,
+//! and scalar signals use [`cgmath::Vector1`].
+
+use cgmath::{num_traits::Float, prelude::*, BaseFloat};
+
+/// Shapes a commanded signal into the applied one. In order, it applies a
+/// deadzone, magnitude saturation, a slew-rate limit, first-order lag and
+/// backlash, each of which is off unless configured.
+#[derive(Clone, Copy, Debug)]
+pub struct Actuator<V: InnerSpace> {
+    deadzone: Option<V::Scalar>,
+    saturation: Option<V::Scalar>,
+    slew_rate: Option<V::Scalar>,
+    time_constant: Option<V::Scalar>,
+    backlash: Option<V::Scalar>,
+
+    // The state after the slew-rate limit, lag and backlash respectively
+    slewed: V,
+    lagged: V,
+    output: V,
+}
+
+impl<V: InnerSpace> Actuator<V>
+where
+    V::Scalar: BaseFloat,
+{
+    /// An actuator applying exactly what is commanded.
+    #[must_use]
+    pub fn ideal() -> Self {
+        Self {
+            deadzone: None,
+            saturation: None,
+            slew_rate: None,
+            time_constant: None,
+            backlash: None,
+            slewed: V::zero(),
+            lagged: V::zero(),
+            output: V::zero(),
+        }
+    }
+    /// Ignore commands smaller than `width`, and subtract it from larger ones
+    /// so that the response stays continuous.
+    #[must_use]
+    pub fn with_deadzone(self, width: V::Scalar) -> Self {
+        Self {
+            deadzone: Some(width),
+            ..self
+        }
+    }
+    /// Limit the magnitude of commands to `max`.
+    #[must_use]
+    pub fn with_saturation(self, max: V::Scalar) -> Self {
+        Self {
+            saturation: Some(max),
+            ..self
+        }
+    }
+    /// Let the command change by at most `max_rate` per unit of time.
+    #[must_use]
+    pub fn with_slew_rate(self, max_rate: V::Scalar) -> Self {
+        Self {
+            slew_rate: Some(max_rate),
+            ..self
+        }
+    }
+    /// Respond like a first-order system, reaching 63% of a step after
+    /// `time_constant`.
+    #[must_use]
+    pub fn with_lag(self, time_constant: V::Scalar) -> Self {
+        Self {
+            time_constant: Some(time_constant),
+            ..self
+        }
+    }
+    /// Play of total `width` in the linkage: the output only follows once the
+    /// input has moved half the width away from it.
+    #[must_use]
+    pub fn with_backlash(self, width: V::Scalar) -> Self {
+        Self {
+            backlash: Some(width),
+            ..self
+        }
+    }
+
+    /// The value currently applied.
+    pub fn output(&self) -> V {
+        self.output
+    }
+
+    // Which of the internal states carry over between updates
+    fn states_in_use(&self) -> [bool; 3] {
+        [
+            self.slew_rate.is_some(),
+            self.time_constant.is_some(),
+            self.backlash.is_some(),
+        ]
+    }
+    // The internal states in use, for linearization
+    pub(crate) fn states(&self) -> Vec<&V> {
+        let used = self.states_in_use();
+        [&self.slewed, &self.lagged, &self.output]
+            .into_iter()
+            .zip(used)
+            .filter_map(|(state, used)| used.then_some(state))
+            .collect()
+    }
+    pub(crate) fn states_mut(&mut self) -> Vec<&mut V> {
+        let used = self.states_in_use();
+        [&mut self.slewed, &mut self.lagged, &mut self.output]
+            .into_iter()
+            .zip(used)
+            .filter_map(|(state, used)| used.then_some(state))
+            .collect()
+    }
+
+    /// Advance by `dt` under `command`, returning the applied value.
+    pub fn update(&mut self, command: V, dt: V::Scalar) -> V {
+        let zero = V::Scalar::zero();
+        let mut target = command;
+        if let Some(width) = self.deadzone {
+            target = shrink(target, width);
+        }
+        if let Some(max) = self.saturation {
+            target = limit(target, max);
+        }
+
+        self.slewed = match self.slew_rate {
+            Some(max_rate) => self.slewed + limit(target - self.slewed, max_rate * dt),
+            None => target,
+        };
+
+        self.lagged = match self.time_constant {
+            Some(time_constant) if time_constant > zero => {
+                let fraction = V::Scalar::one() - Float::exp(-dt / time_constant);
+                self.lagged + (self.slewed - self.lagged) * fraction
+            }
+            _ => self.slewed,
+        };
+
+        self.output = match self.backlash {
+            Some(width) => {
+                let half_width = width / (V::Scalar::one() + V::Scalar::one());
+                self.lagged - limit(self.lagged - self.output, half_width)
+            }
+            None => self.lagged,
+        };
+        self.output
+    }
+}
+
+impl<V: InnerSpace> Default for Actuator<V>
+where
+    V::Scalar: BaseFloat,
+{
+    fn default() -> Self {
+        Self::ideal()
+    }
+}
+
+// `v` with its magnitude limited to `max`
+fn limit<V: InnerSpace>(v: V, max: V::Scalar) -> V
+where
+    V::Scalar: BaseFloat,
+{
+    let magnitude = v.magnitude();
+    if magnitude > max {
+        v * (max / magnitude)
+    } else {
+        v
+    }
+}
+
+// `v` with its magnitude reduced by `amount`, but not past zero
+fn shrink<V: InnerSpace>(v: V, amount: V::Scalar) -> V
+where
+    V::Scalar: BaseFloat,
+{
+    let magnitude = v.magnitude();
+    if magnitude > amount {
+        v * ((magnitude - amount) / magnitude)
+    } else {
+        V::zero()
+    }
+}
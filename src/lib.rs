@@ -29,8 +29,12 @@
     nonstandard_style
 )]
 
+pub mod actuators;
+pub mod linearization;
 pub mod models;
+pub mod multi_agent;
 pub mod physics;
+pub mod sensors;
 pub use solid::Solid;
 
 mod run; // The simulation thread loop
@@ -84,6 +88,9 @@ where
 pub struct Status {
     pub display_visual: bool,
     pub should_quit: bool,
+    /// Log how the energy reported by [`Model::diagnostics`] drifts, and warn
+    /// if it grows by more than this fraction of its initial value.
+    pub max_energy_growth: Option<f32>,
 }
 
 impl Status {
@@ -94,6 +101,7 @@ impl Status {
     pub const HEADLESS: Status = Status {
         display_visual: false,
         should_quit: false,
+        max_energy_growth: None,
     };
 }
 
@@ -110,4 +118,9 @@ pub trait Model {
     fn update(world: &mut Self::World, signals: &Self::Signals);
 
     fn get_solids(world: &Self::World) -> Vec<Solid>;
+
+    /// Energy and momentum of the world, if meaningful for this model.
+    fn diagnostics(_world: &Self::World) -> Option<physics::diagnostics::Diagnostics> {
+        None
+    }
 }
@@ -0,0 +1,543 @@
+//! Numerical linearization of models, for designing linear controllers such
+//! as LQR or pole placement.
+//!
+//! A model can be linearized once its world and signals implement
+//! [`Flatten`]. [`linearize`] then finite-differences [`Model::update`]
+//! around an operating point, giving the discrete-time system
+//! `x' = A x + B u`, one tick per step.
+
+use crate::{
+    actuators::Actuator,
+    physics::{Particle, RigidBody},
+    Model,
+};
+use cgmath::{prelude::*, BaseFloat, Quaternion, Vector1, Vector2, Vector3};
+use std::ops::{Index, IndexMut, Mul};
+
+/// Conversion to and from a flat list of numbers, covering the state of a
+/// world or the inputs of some signals. Whatever is left out, such as the time
+/// or contact events, is treated as a constant.
+pub trait Flatten {
+    /// Append every number to `values`.
+    fn flatten_into(&self, values: &mut Vec<f64>);
+    /// Read back the numbers written by [`Flatten::flatten_into`], from the
+    /// start of `values`, returning the rest.
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64];
+
+    fn flatten(&self) -> Vec<f64> {
+        let mut values = Vec::new();
+        self.flatten_into(&mut values);
+        values
+    }
+    fn unflatten(&mut self, values: &[f64]) {
+        let rest = self.unflatten_from(values);
+        assert!(rest.is_empty(), "{} values left over", rest.len());
+    }
+}
+
+impl Flatten for f32 {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        values.push(f64::from(*self));
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        *self = values[0] as f32;
+        &values[1..]
+    }
+}
+
+impl Flatten for f64 {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        values.push(*self);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        *self = values[0];
+        &values[1..]
+    }
+}
+
+impl<S: BaseFloat + Flatten> Flatten for Vector1<S> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.x.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        self.x.unflatten_from(values)
+    }
+}
+
+impl<S: BaseFloat + Flatten> Flatten for Vector2<S> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.x.flatten_into(values);
+        self.y.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.x.unflatten_from(values);
+        self.y.unflatten_from(values)
+    }
+}
+
+impl<S: BaseFloat + Flatten> Flatten for Vector3<S> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.x.flatten_into(values);
+        self.y.flatten_into(values);
+        self.z.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.x.unflatten_from(values);
+        let values = self.y.unflatten_from(values);
+        self.z.unflatten_from(values)
+    }
+}
+
+/// All four components, so a linearization has one direction too many for
+/// orientations, along which nothing changes.
+impl<S: BaseFloat + Flatten> Flatten for Quaternion<S> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.s.flatten_into(values);
+        self.v.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.s.unflatten_from(values);
+        self.v.unflatten_from(values)
+    }
+}
+
+/// The position and velocity. The radius is a constant.
+impl<S: BaseFloat + Flatten> Flatten for Particle<S> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.pos.flatten_into(values);
+        self.vel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.pos.unflatten_from(values);
+        self.vel.unflatten_from(values)
+    }
+}
+
+/// The position, orientation and their rates. The mass and inertia are
+/// constants.
+impl<S: BaseFloat + Flatten> Flatten for RigidBody<S> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.pos.flatten_into(values);
+        self.vel.flatten_into(values);
+        self.orientation.flatten_into(values);
+        self.angular_vel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.pos.unflatten_from(values);
+        let values = self.vel.unflatten_from(values);
+        let values = self.orientation.unflatten_from(values);
+        self.angular_vel.unflatten_from(values)
+    }
+}
+
+/// The internal states in use, so that actuator dynamics become part of the
+/// model.
+impl<V: InnerSpace + Flatten> Flatten for Actuator<V>
+where
+    V::Scalar: BaseFloat,
+{
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        for state in self.states() {
+            state.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, mut values: &'a [f64]) -> &'a [f64] {
+        for state in self.states_mut() {
+            values = state.unflatten_from(values);
+        }
+        values
+    }
+}
+
+/// A dense, row-major matrix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    #[must_use]
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![0.0; rows * cols],
+        }
+    }
+    #[must_use]
+    pub fn identity(size: usize) -> Self {
+        let mut identity = Self::zeros(size, size);
+        for i in 0..size {
+            identity[(i, i)] = 1.0;
+        }
+        identity
+    }
+    /// A matrix with the given rows.
+    ///
+    /// # Panics
+    ///
+    /// If the rows differ in length.
+    #[must_use]
+    pub fn from_rows(rows: &[&[f64]]) -> Self {
+        let cols = rows.first().map_or(0, |row| row.len());
+        assert!(rows.iter().all(|row| row.len() == cols), "ragged rows");
+        Self {
+            rows: rows.len(),
+            cols,
+            data: rows.concat(),
+        }
+    }
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+    #[must_use]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+    #[must_use]
+    pub fn transpose(&self) -> Self {
+        let mut transpose = Self::zeros(self.cols, self.rows);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                transpose[(c, r)] = self[(r, c)];
+            }
+        }
+        transpose
+    }
+    /// `self` with the columns of `other` appended.
+    ///
+    /// # Panics
+    ///
+    /// If the matrices differ in their number of rows.
+    #[must_use]
+    pub fn hstack(&self, other: &Self) -> Self {
+        assert_eq!(self.rows, other.rows);
+        let mut stacked = Self::zeros(self.rows, self.cols + other.cols);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                stacked[(r, c)] = self[(r, c)];
+            }
+            for c in 0..other.cols {
+                stacked[(r, self.cols + c)] = other[(r, c)];
+            }
+        }
+        stacked
+    }
+
+    /// The number of linearly independent rows, with entries smaller than
+    /// `tolerance` times the largest treated as zero.
+    #[must_use]
+    pub fn rank(&self, tolerance: f64) -> usize {
+        let mut m = self.clone();
+        let largest = m.data.iter().fold(0.0, |max: f64, x| max.max(x.abs()));
+        let threshold = tolerance * largest;
+        let mut rank = 0;
+
+        // Gaussian elimination with partial pivoting
+        for c in 0..m.cols {
+            if rank == m.rows {
+                break;
+            }
+            let pivot = (rank + 1..m.rows).fold(rank, |pivot, r| {
+                if m[(r, c)].abs() > m[(pivot, c)].abs() {
+                    r
+                } else {
+                    pivot
+                }
+            });
+            if m[(pivot, c)].abs() <= threshold {
+                continue;
+            }
+            for k in 0..m.cols {
+                m.data.swap(pivot * m.cols + k, rank * m.cols + k);
+            }
+            for r in rank + 1..m.rows {
+                let factor = m[(r, c)] / m[(rank, c)];
+                for k in c..m.cols {
+                    let above = m[(rank, k)];
+                    m[(r, k)] -= factor * above;
+                }
+            }
+            rank += 1;
+        }
+        rank
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+    fn index(&self, (row, col): (usize, usize)) -> &f64 {
+        assert!(row < self.rows && col < self.cols);
+        &self.data[row * self.cols + col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut f64 {
+        assert!(row < self.rows && col < self.cols);
+        &mut self.data[row * self.cols + col]
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Matrix;
+    fn mul(self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows);
+        let mut product = Matrix::zeros(self.rows, other.cols);
+        for r in 0..self.rows {
+            for k in 0..self.cols {
+                let left = self[(r, k)];
+                for c in 0..other.cols {
+                    product[(r, c)] += left * other[(k, c)];
+                }
+            }
+        }
+        product
+    }
+}
+
+// How much of a vector must be new to count as a new direction. Finite
+// differences of `f32` worlds are only accurate to about this much.
+const RANK_TOLERANCE: f64 = 1e-6;
+
+/// The discrete-time system `x' = A x + B u` around an operating point, where
+/// `x` and `u` are deviations of the flattened world and signals.
+#[derive(Clone, Debug)]
+pub struct Linearization {
+    pub a: Matrix,
+    pub b: Matrix,
+}
+
+impl Linearization {
+    /// `[B, AB, A²B, ...]`, with as many blocks as there are states.
+    #[must_use]
+    pub fn controllability_matrix(&self) -> Matrix {
+        let mut block = self.b.clone();
+        let mut matrix = block.clone();
+        for _ in 1..self.a.rows() {
+            block = &self.a * &block;
+            matrix = matrix.hstack(&block);
+        }
+        matrix
+    }
+    /// `[C; CA; CA²; ...]`, with as many blocks as there are states.
+    #[must_use]
+    pub fn observability_matrix(&self, c: &Matrix) -> Matrix {
+        self.dual(c).controllability_matrix().transpose()
+    }
+
+    /// The dimension of the subspace reachable with some inputs, i.e. the
+    /// rank of the controllability matrix.
+    ///
+    /// The controllability matrix itself is badly conditioned for stiff
+    /// systems, so this orthonormalizes as it goes instead.
+    #[must_use]
+    pub fn controllable_dimension(&self) -> usize {
+        krylov_dimension(&self.a, &self.b, RANK_TOLERANCE)
+    }
+    /// The dimension of the subspace that shows in the outputs `y = C x`,
+    /// i.e. the rank of the observability matrix.
+    ///
+    /// # Panics
+    ///
+    /// If `c` does not have one column per state.
+    #[must_use]
+    pub fn observable_dimension(&self, c: &Matrix) -> usize {
+        assert_eq!(c.cols(), self.a.cols());
+        self.dual(c).controllable_dimension()
+    }
+    /// Whether any state can be reached from any other with some inputs.
+    #[must_use]
+    pub fn is_controllable(&self) -> bool {
+        self.controllable_dimension() == self.a.rows()
+    }
+    /// Whether the state can be inferred from the outputs `y = C x` over time.
+    #[must_use]
+    pub fn is_observable(&self, c: &Matrix) -> bool {
+        self.observable_dimension(c) == self.a.rows()
+    }
+
+    // The system whose controllability is the observability of this one
+    fn dual(&self, c: &Matrix) -> Self {
+        Self {
+            a: self.a.transpose(),
+            b: c.transpose(),
+        }
+    }
+}
+
+// The dimension of the span of `[B, AB, A²B, ...]`, built up one orthonormal
+// direction at a time
+fn krylov_dimension(a: &Matrix, b: &Matrix, tolerance: f64) -> usize {
+    let n = a.rows();
+    let column = |m: &Matrix, c: usize| (0..m.rows()).map(|r| m[(r, c)]).collect::<Vec<_>>();
+    let dot = |x: &[f64], y: &[f64]| x.iter().zip(y).map(|(x, y)| x * y).sum::<f64>();
+
+    let mut basis: Vec<Vec<f64>> = Vec::new();
+    let mut frontier: Vec<Vec<f64>> = (0..b.cols()).map(|c| column(b, c)).collect();
+    while !frontier.is_empty() && basis.len() < n {
+        let mut added = Vec::new();
+        for mut v in frontier {
+            let norm = dot(&v, &v).sqrt();
+            // Project out the basis twice, which is enough for stability
+            for _ in 0..2 {
+                for q in &basis {
+                    let along = dot(&v, q);
+                    v.iter_mut().zip(q).for_each(|(v, q)| *v -= along * q);
+                }
+            }
+            let remaining = dot(&v, &v).sqrt();
+            if norm > 0.0 && remaining > tolerance * norm {
+                for v in &mut v {
+                    *v /= remaining;
+                }
+                basis.push(v.clone());
+                added.push(v);
+            }
+        }
+        frontier = added
+            .iter()
+            .map(|q| {
+                (0..n)
+                    .map(|r| (0..n).map(|k| a[(r, k)] * q[k]).sum())
+                    .collect()
+            })
+            .collect();
+    }
+    basis.len()
+}
+
+/// Linearize one tick of model `M` around `world` and `signals`, with central
+/// differences of size `step` in every flattened coordinate.
+///
+/// Signals are built from [`Model::new_signals`] and then overwritten with the
+/// flattened `signals`, so everything not flattened takes its default value.
+pub fn linearize<M: Model>(world: &M::World, signals: &M::Signals, step: f64) -> Linearization
+where
+    M::World: Flatten,
+    M::Signals: Flatten,
+{
+    let x0 = world.flatten();
+    let u0 = signals.flatten();
+
+    let next_state = |x: &[f64], u: &[f64]| {
+        let mut world = world.clone();
+        world.unflatten(x);
+        let mut signals = M::new_signals();
+        signals.unflatten(u);
+        M::update(&mut world, &signals);
+        world.flatten()
+    };
+    // Central difference along one coordinate, as a column
+    let derivative = |perturb: &dyn Fn(f64) -> Vec<f64>| -> Vec<f64> {
+        let (plus, minus) = (perturb(step), perturb(-step));
+        plus.iter()
+            .zip(minus)
+            .map(|(plus, minus)| (plus - minus) / (2.0 * step))
+            .collect()
+    };
+
+    let mut a = Matrix::zeros(x0.len(), x0.len());
+    for i in 0..x0.len() {
+        let column = derivative(&|delta| {
+            let mut x = x0.clone();
+            x[i] += delta;
+            next_state(&x, &u0)
+        });
+        for (r, value) in column.into_iter().enumerate() {
+            a[(r, i)] = value;
+        }
+    }
+    let mut b = Matrix::zeros(x0.len(), u0.len());
+    for i in 0..u0.len() {
+        let column = derivative(&|delta| {
+            let mut u = u0.clone();
+            u[i] += delta;
+            next_state(&x0, &u)
+        });
+        for (r, value) in column.into_iter().enumerate() {
+            b[(r, i)] = value;
+        }
+    }
+    Linearization { a, b }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SimpleModel;
+
+    fn assert_close(actual: &Matrix, expected: &Matrix, tolerance: f64) {
+        assert_eq!(
+            (actual.rows(), actual.cols()),
+            (expected.rows(), expected.cols())
+        );
+        for r in 0..actual.rows() {
+            for c in 0..actual.cols() {
+                assert!(
+                    (actual[(r, c)] - expected[(r, c)]).abs() < tolerance,
+                    "({r}, {c}) is {}, expected {}",
+                    actual[(r, c)],
+                    expected[(r, c)],
+                );
+            }
+        }
+    }
+
+    // Fill the 3x3 block at `(row, col)` with `value` times the identity
+    fn set_block(m: &mut Matrix, (row, col): (usize, usize), value: f64) {
+        for i in 0..3 {
+            m[(row + i, col + i)] = value;
+        }
+    }
+
+    #[test]
+    fn simple_model_matches_its_analytic_linearization() {
+        // Position, velocity and color under an ideal actuator, so
+        //   pos' = pos + vel dt + accel dt^2 / 2
+        //   vel' = vel + accel dt
+        //   color' = (1 - dt) color + target dt
+        let dt = 0.01;
+        let mut a = Matrix::identity(9);
+        set_block(&mut a, (0, 3), dt);
+        set_block(&mut a, (6, 6), 1.0 - dt);
+        let mut b = Matrix::zeros(9, 6);
+        set_block(&mut b, (0, 0), dt * dt / 2.0);
+        set_block(&mut b, (3, 0), dt);
+        set_block(&mut b, (6, 3), dt);
+
+        // The model is linear, so large steps only shrink rounding errors
+        let linearization =
+            linearize::<SimpleModel>(&SimpleModel::new_world(), &SimpleModel::new_signals(), 0.1);
+        assert_close(&linearization.a, &a, 1e-5);
+        assert_close(&linearization.b, &b, 1e-5);
+
+        assert!(linearization.is_controllable());
+        // Positions reveal velocities, but nothing reveals the color
+        let mut positions = Matrix::zeros(3, 9);
+        set_block(&mut positions, (0, 0), 1.0);
+        assert_eq!(linearization.observable_dimension(&positions), 6);
+    }
+
+    #[test]
+    fn krylov_dimension_matches_the_controllability_rank() {
+        // A double integrator along x, and an undriven mode along y
+        let double_integrator = Linearization {
+            a: Matrix::from_rows(&[&[1.0, 0.1, 0.0], &[0.0, 1.0, 0.0], &[0.0, 0.0, 0.5]]),
+            b: Matrix::from_rows(&[&[0.005], &[0.1], &[0.0]]),
+        };
+        assert_eq!(double_integrator.controllability_matrix().rank(1e-9), 2);
+        assert_eq!(double_integrator.controllable_dimension(), 2);
+        assert!(!double_integrator.is_controllable());
+        let position = Matrix::from_rows(&[&[1.0, 0.0, 0.0]]);
+        assert_eq!(double_integrator.observable_dimension(&position), 2);
+        assert_eq!(
+            double_integrator.observability_matrix(&position).rank(1e-9),
+            2
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use super::{pendulum::wrap_angle, Episode};
+use crate::{linearization::Flatten, Model, Solid};
+use cgmath::Vector3;
+use std::f32::consts::PI;
+
+#[derive(Clone)]
+pub struct AcrobotWorld {
+    /// The angle of the first link from hanging down, positive towards +x.
+    pub theta1: f32,
+    /// The angle of the second link relative to the first.
+    pub theta2: f32,
+    pub theta1_vel: f32,
+    pub theta2_vel: f32,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+impl AcrobotWorld {
+    /// The height of the tip above the shoulder, in link lengths.
+    pub fn tip_height(&self) -> f32 {
+        -self.theta1.cos() - (self.theta1 + self.theta2).cos()
+    }
+}
+
+pub struct AcrobotSignals {
+    /// The torque at the elbow, limited to [`Acrobot::MAX_TORQUE`] in either
+    /// direction. The reference only allows -1, 0 and 1.
+    pub torque: f32,
+}
+
+impl Flatten for AcrobotWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.theta1.flatten_into(values);
+        self.theta2.flatten_into(values);
+        self.theta1_vel.flatten_into(values);
+        self.theta2_vel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.theta1.unflatten_from(values);
+        let values = self.theta2.unflatten_from(values);
+        let values = self.theta1_vel.unflatten_from(values);
+        self.theta2_vel.unflatten_from(values)
+    }
+}
+
+impl Flatten for AcrobotSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.torque.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        self.torque.unflatten_from(values)
+    }
+}
+
+/// A two-link arm hanging from a fixed shoulder, driven only at the elbow.
+/// Swing the tip above [`Acrobot::GOAL_HEIGHT`] as fast as possible.
+///
+/// The constants, dynamics and reward are those of Gym's `Acrobot-v1`: each
+/// tick costs 1 until the tip reaches the goal, which terminates the episode.
+/// Episodes are truncated after [`Acrobot::MAX_STEPS`].
+pub struct Acrobot;
+
+impl Acrobot {
+    pub const GRAVITY: f32 = 9.8;
+    pub const LINK_LENGTH: [f32; 2] = [1.0, 1.0];
+    pub const LINK_MASS: [f32; 2] = [1.0, 1.0];
+    /// The distance from each joint to the center of mass of its link.
+    pub const LINK_COM: [f32; 2] = [0.5, 0.5];
+    pub const LINK_MOI: f32 = 1.0;
+    pub const MAX_VEL: [f32; 2] = [4.0 * PI, 9.0 * PI];
+    pub const MAX_TORQUE: f32 = 1.0;
+    /// The time per tick.
+    pub const DT: f32 = 0.2;
+    /// The tip height, in link lengths above the shoulder, that ends the
+    /// episode.
+    pub const GOAL_HEIGHT: f32 = 1.0;
+    pub const MAX_STEPS: u32 = 500;
+
+    /// The accelerations of both joints, as in "Reinforcement Learning: An
+    /// Introduction" by Sutton and Barto.
+    fn accels([theta1, theta2, theta1_vel, theta2_vel]: [f32; 4], torque: f32) -> [f32; 2] {
+        let [m1, m2] = Self::LINK_MASS;
+        let [l1, _] = Self::LINK_LENGTH;
+        let [lc1, lc2] = Self::LINK_COM;
+        let (i1, i2) = (Self::LINK_MOI, Self::LINK_MOI);
+        let g = Self::GRAVITY;
+
+        let d1 =
+            m1 * lc1 * lc1 + m2 * (l1 * l1 + lc2 * lc2 + 2.0 * l1 * lc2 * theta2.cos()) + i1 + i2;
+        let d2 = m2 * (lc2 * lc2 + l1 * lc2 * theta2.cos()) + i2;
+        let phi2 = m2 * lc2 * g * (theta1 + theta2 - PI / 2.0).cos();
+        let phi1 = -m2 * l1 * lc2 * theta2_vel * theta2_vel * theta2.sin()
+            - 2.0 * m2 * l1 * lc2 * theta2_vel * theta1_vel * theta2.sin()
+            + (m1 * lc1 + m2 * l1) * g * (theta1 - PI / 2.0).cos()
+            + phi2;
+        let theta2_accel = (torque + d2 / d1 * phi1
+            - m2 * l1 * lc2 * theta1_vel * theta1_vel * theta2.sin()
+            - phi2)
+            / (m2 * lc2 * lc2 + i2 - d2 * d2 / d1);
+        let theta1_accel = -(d2 * theta2_accel + phi1) / d1;
+        [theta1_accel, theta2_accel]
+    }
+}
+
+impl Model for Acrobot {
+    type World = AcrobotWorld;
+    type Signals = AcrobotSignals;
+
+    fn new_world() -> Self::World {
+        let initial = || (fastrand::f32() - 0.5) / 5.0;
+        Self::World {
+            theta1: initial(),
+            theta2: initial(),
+            theta1_vel: initial(),
+            theta2_vel: initial(),
+            episode: Episode::default(),
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals { torque: 0.0 }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        let torque = signals.torque.clamp(-Self::MAX_TORQUE, Self::MAX_TORQUE);
+        // One RK4 step over the whole tick, as in the reference
+        let derivative = |[theta1, theta2, theta1_vel, theta2_vel]: [f32; 4]| {
+            let [theta1_accel, theta2_accel] =
+                Self::accels([theta1, theta2, theta1_vel, theta2_vel], torque);
+            [theta1_vel, theta2_vel, theta1_accel, theta2_accel]
+        };
+        let offset = |state: [f32; 4], slope: [f32; 4], dt: f32| {
+            let mut state = state;
+            for (x, dx) in state.iter_mut().zip(slope) {
+                *x += dx * dt;
+            }
+            state
+        };
+        let state = [w.theta1, w.theta2, w.theta1_vel, w.theta2_vel];
+        let k1 = derivative(state);
+        let k2 = derivative(offset(state, k1, Self::DT / 2.0));
+        let k3 = derivative(offset(state, k2, Self::DT / 2.0));
+        let k4 = derivative(offset(state, k3, Self::DT));
+        let mut slope = [0.0; 4];
+        for (i, slope) in slope.iter_mut().enumerate() {
+            *slope = (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]) / 6.0;
+        }
+        let [theta1, theta2, theta1_vel, theta2_vel] = offset(state, slope, Self::DT);
+
+        let [max_vel1, max_vel2] = Self::MAX_VEL;
+        w.theta1 = wrap_angle(theta1);
+        w.theta2 = wrap_angle(theta2);
+        w.theta1_vel = theta1_vel.clamp(-max_vel1, max_vel1);
+        w.theta2_vel = theta2_vel.clamp(-max_vel2, max_vel2);
+        w.time += Self::DT;
+
+        let terminated = w.tip_height() > Self::GOAL_HEIGHT;
+        let reward = if terminated { 0.0 } else { -1.0 };
+        w.episode.record(reward, terminated, Some(Self::MAX_STEPS));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const SHOULDER_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+        const ELBOW_COLOR: Vector3<f32> = Vector3::new(0.9, 0.6, 0.1);
+        const NODE_COLOR: Vector3<f32> = Vector3::new(0.5, 0.2, 0.3);
+        const ROD_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
+        const GOAL_COLOR: Vector3<f32> = Vector3::new(0.7, 0.7, 0.7);
+
+        const SHOULDER: Vector3<f32> = Vector3::new(0.0, 0.0, 2.5);
+        const NODE_RADIUS: f32 = 0.15;
+        const ROD_RADIUS: f32 = 0.05;
+        const GOAL_RADIUS: f32 = 0.01;
+
+        let [l1, l2] = Acrobot::LINK_LENGTH;
+        let down = |angle: f32| Vector3::new(angle.sin(), 0.0, -angle.cos());
+        let elbow = SHOULDER + down(world.theta1) * l1;
+        let tip = elbow + down(world.theta1 + world.theta2) * l2;
+        let goal = SHOULDER + Vector3::unit_z() * (Acrobot::GOAL_HEIGHT * l1);
+        vec![
+            Solid::new_sphere(SHOULDER, NODE_RADIUS / 2.0, SHOULDER_COLOR),
+            Solid::new_cylinder(SHOULDER, elbow, ROD_RADIUS, ROD_COLOR),
+            Solid::new_sphere(elbow, NODE_RADIUS, ELBOW_COLOR),
+            Solid::new_cylinder(elbow, tip, ROD_RADIUS, ROD_COLOR),
+            Solid::new_sphere(tip, NODE_RADIUS, NODE_COLOR),
+            Solid::new_cylinder(
+                goal - Vector3::unit_x() * (l1 + l2),
+                goal + Vector3::unit_x() * (l1 + l2),
+                GOAL_RADIUS,
+                GOAL_COLOR,
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world_at([theta1, theta2, theta1_vel, theta2_vel]: [f32; 4]) -> AcrobotWorld {
+        AcrobotWorld {
+            theta1,
+            theta2,
+            theta1_vel,
+            theta2_vel,
+            ..Acrobot::new_world()
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn constants_match_the_reference() {
+        assert_close(Acrobot::GRAVITY, 9.8);
+        let pairs = [
+            (Acrobot::LINK_LENGTH, [1.0, 1.0]),
+            (Acrobot::LINK_MASS, [1.0, 1.0]),
+            (Acrobot::LINK_COM, [0.5, 0.5]),
+            (Acrobot::MAX_VEL, [4.0 * PI, 9.0 * PI]),
+        ];
+        for (actual, expected) in pairs {
+            assert_close(actual[0], expected[0]);
+            assert_close(actual[1], expected[1]);
+        }
+        assert_close(Acrobot::LINK_MOI, 1.0);
+        assert_close(Acrobot::MAX_TORQUE, 1.0);
+        assert_close(Acrobot::DT, 0.2);
+        assert_eq!(Acrobot::MAX_STEPS, 500);
+    }
+
+    #[test]
+    fn a_step_matches_the_reference() {
+        let mut world = world_at([0.1, -0.2, 0.3, -0.4]);
+        Acrobot::update(&mut world, &AcrobotSignals { torque: 1.0 });
+
+        // The reference's "book" dynamics with its RK4 step, in f64
+        let expected = [0.128_211_63, -0.211_840_32, -0.023_484_73, 0.286_795_21];
+        let actual = [
+            world.theta1,
+            world.theta2,
+            world.theta1_vel,
+            world.theta2_vel,
+        ];
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual - expected).abs() < 1e-5,
+                "{actual:?} != {expected:?}"
+            );
+        }
+        assert_close(world.episode.reward, -1.0);
+        assert!(!world.episode.is_over());
+    }
+
+    #[test]
+    fn reaching_the_goal_terminates() {
+        // Both links pointing straight up, and staying there for a tick
+        let mut world = world_at([PI, 0.0, 0.0, 0.0]);
+        Acrobot::update(&mut world, &Acrobot::new_signals());
+        assert!(world.tip_height() > Acrobot::GOAL_HEIGHT);
+        assert!(world.episode.terminated);
+        assert_close(world.episode.reward, 0.0);
+    }
+}
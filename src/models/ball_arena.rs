@@ -0,0 +1,260 @@
+use super::Episode;
+use crate::{
+    linearization::Flatten,
+    physics::{
+        self, diagnostics::Diagnostics, par_accelerations, scalar, Collider, ContactEvent, Cuboid,
+        ForceField, Gravity, Particle, ParticleSet, ParticleView, Plane, Rk4Scratch,
+    },
+    Model, Solid,
+};
+use cgmath::{prelude::*, Quaternion, Vector2, Vector3};
+
+#[derive(Clone)]
+pub struct BallArenaWorld<const N: usize> {
+    pub balls: [Particle; N],
+    /// The center of the goal on the floor.
+    pub goal: Vector2<f32>,
+    /// Contacts during the last tick. The balls are particles `0..N`, the
+    /// floor is collider 0 and the walls are colliders 1 to 4.
+    pub contacts: Vec<ContactEvent>,
+
+    pub episode: Episode,
+    pub time: f32,
+
+    // Buffers reused between ticks
+    particles: ParticleSet,
+    scratch: Rk4Scratch,
+}
+
+impl<const N: usize> BallArenaWorld<N> {
+    /// Whether `ball` is within [`BallArena::GOAL_RADIUS`] of the goal, seen
+    /// from above.
+    pub fn is_in_goal(&self, ball: &Particle) -> bool {
+        ball.pos.truncate().distance(self.goal) < BallArena::<N>::GOAL_RADIUS
+    }
+}
+
+pub struct BallArenaSignals {
+    /// Which ball to push. Out of range indices push nothing.
+    pub ball: usize,
+    /// The horizontal change in velocity given to the ball each tick, limited
+    /// to [`BallArena::MAX_IMPULSE`] in magnitude.
+    pub impulse: Vector2<f32>,
+}
+
+impl<const N: usize> Flatten for BallArenaWorld<N> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        for ball in &self.balls {
+            ball.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, mut values: &'a [f64]) -> &'a [f64] {
+        for ball in &mut self.balls {
+            values = ball.unflatten_from(values);
+        }
+        values
+    }
+}
+
+/// Only the impulse, as the choice of ball is discrete.
+impl Flatten for BallArenaSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.impulse.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        self.impulse.unflatten_from(values)
+    }
+}
+
+/// `N` balls dropped into a walled square, where they bounce off the floor,
+/// the walls and each other. Herd them into a goal by pushing one ball at a
+/// time.
+///
+/// Each tick earns the fraction of balls in the goal. The episode terminates
+/// once all of them are, and is otherwise truncated after
+/// [`BallArena::MAX_STEPS`].
+pub struct BallArena<const N: usize>;
+
+impl<const N: usize> BallArena<N> {
+    pub const RADIUS: f32 = 0.3;
+    /// The side length of the square inside the walls, centered on the origin.
+    pub const ARENA_SIZE: f32 = 9.6;
+    pub const GOAL_RADIUS: f32 = 1.5;
+    pub const MAX_IMPULSE: f32 = 0.5;
+    pub const MAX_STEPS: u32 = 2000;
+}
+
+const GRAVITY: Gravity = Gravity {
+    accel: Vector3::new(0.0, 0.0, -4.0),
+};
+const SUBSTEPS: usize = 5;
+const WALL_THICKNESS: f32 = 0.2;
+const WALL_HEIGHT: f32 = 1.5;
+// Balls start in layers of a grid this many cells across, spaced so that
+// none overlap
+const GRID_SIDE: usize = 12;
+const DROP_HEIGHT: f32 = 1.0;
+
+// The four walls around the arena, reaching the edges of the floor
+fn walls(arena_size: f32) -> [Cuboid; 4] {
+    let length = arena_size + 2.0 * WALL_THICKNESS;
+    let offset = arena_size / 2.0 + WALL_THICKNESS / 2.0;
+    [
+        (Vector2::unit_x(), Vector2::new(WALL_THICKNESS, length)),
+        (-Vector2::unit_x(), Vector2::new(WALL_THICKNESS, length)),
+        (Vector2::unit_y(), Vector2::new(length, WALL_THICKNESS)),
+        (-Vector2::unit_y(), Vector2::new(length, WALL_THICKNESS)),
+    ]
+    .map(|(side, dimensions)| {
+        Cuboid::new(
+            dimensions.extend(WALL_HEIGHT),
+            (side * offset).extend(WALL_HEIGHT / 2.0),
+            Quaternion::one(),
+        )
+    })
+}
+
+impl<const N: usize> Model for BallArena<N> {
+    type World = BallArenaWorld<N>;
+    type Signals = BallArenaSignals;
+
+    fn new_world() -> Self::World {
+        // Fill each layer in a random order, jiggled a little
+        let spacing = Self::ARENA_SIZE / scalar::<f32>(GRID_SIDE);
+        let mut cells: Vec<usize> = (0..GRID_SIDE * GRID_SIDE).collect();
+        fastrand::shuffle(&mut cells);
+        let corner = (spacing - Self::ARENA_SIZE) / 2.0;
+        let jiggle = || (fastrand::f32() - 0.5) * (spacing - 2.0 * Self::RADIUS);
+        let balls = std::array::from_fn(|i| {
+            let cell = cells[i % cells.len()];
+            let layer = i / cells.len();
+            let pos = Vector3::new(
+                corner + scalar::<f32>(cell % GRID_SIDE) * spacing + jiggle(),
+                corner + scalar::<f32>(cell / GRID_SIDE) * spacing + jiggle(),
+                DROP_HEIGHT + scalar::<f32>(layer) * spacing,
+            );
+            Particle::new(pos, Vector3::zero(), Self::RADIUS)
+        });
+
+        let half_size = Self::ARENA_SIZE / 2.0 - Self::GOAL_RADIUS;
+        let coordinate = || (fastrand::f32() * 2.0 - 1.0) * half_size;
+        Self::World {
+            balls,
+            goal: Vector2::new(coordinate(), coordinate()),
+            contacts: Vec::new(),
+            episode: Episode::default(),
+            time: 0.0,
+            particles: ParticleSet::new(),
+            scratch: Rk4Scratch::new(),
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            ball: 0,
+            impulse: Vector2::zero(),
+        }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        if let Some(ball) = w.balls.get_mut(signals.ball) {
+            let mut impulse = signals.impulse;
+            if impulse.magnitude() > Self::MAX_IMPULSE {
+                impulse = impulse.normalize_to(Self::MAX_IMPULSE);
+            }
+            ball.vel += impulse.extend(0.0);
+        }
+
+        let walls = walls(Self::ARENA_SIZE);
+        let particles = &mut w.particles;
+        particles.clear();
+        particles.extend(w.balls);
+        w.contacts.clear();
+        for _ in 0..SUBSTEPS {
+            physics::step_with_rk4(particles, &mut w.scratch, &(w.time, walls), accels);
+            w.time += physics::dt::<f32>();
+
+            for (i, ball) in particles.iter().enumerate() {
+                w.contacts.extend(Plane::FLOOR.contact_event(0, &ball, i));
+                for (j, wall) in walls.iter().enumerate() {
+                    w.contacts.extend(wall.contact_event(j + 1, &ball, i));
+                }
+                for j in 0..i {
+                    w.contacts
+                        .extend(ball.collision_event_with(i, &particles.get(j), j));
+                }
+            }
+        }
+        for (i, ball) in w.balls.iter_mut().enumerate() {
+            *ball = w.particles.get(i);
+        }
+
+        let in_goal = w.balls.iter().filter(|ball| w.is_in_goal(ball)).count();
+        w.episode.record(
+            scalar::<f32>(in_goal) / scalar::<f32>(N),
+            in_goal == N,
+            Some(Self::MAX_STEPS),
+        );
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const BALL_COLOR: Vector3<f32> = Vector3::new(0.5, 0.5, 0.2);
+        const SCORED_COLOR: Vector3<f32> = Vector3::new(0.9, 0.6, 0.1);
+        const WALL_COLOR: Vector3<f32> = Vector3::new(0.6, 0.6, 0.6);
+        const GOAL_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+        const FLOOR_COLOR: Vector3<f32> = Vector3::new(0.9, 0.9, 0.9);
+
+        const GOAL_THICKNESS: f32 = 0.01;
+
+        let mut solids = vec![Solid::new_cylinder(
+            world.goal.extend(0.0),
+            world.goal.extend(GOAL_THICKNESS),
+            Self::GOAL_RADIUS,
+            GOAL_COLOR,
+        )];
+        for ball in &world.balls {
+            let color = if world.is_in_goal(ball) {
+                SCORED_COLOR
+            } else {
+                BALL_COLOR
+            };
+            solids.push(Solid::new_sphere(ball.pos, Self::RADIUS, color));
+        }
+        for wall in walls(Self::ARENA_SIZE) {
+            solids.extend(wall.solids(WALL_COLOR));
+        }
+        solids.extend(Plane::FLOOR.solids(FLOOR_COLOR));
+        solids
+    }
+
+    fn diagnostics(world: &Self::World) -> Option<Diagnostics> {
+        Some(Diagnostics::measure(&world.balls, GRAVITY.accel, []))
+    }
+}
+
+fn accels(
+    particles: ParticleView<'_>,
+    &(time, walls): &(f32, [Cuboid; 4]),
+    accels: &mut [Vector3<f32>],
+) {
+    par_accelerations(particles, accels, |particles, i| {
+        let ball = particles.get(i);
+        let mut accel = Plane::FLOOR.collide_with(&ball) + GRAVITY.accel(&ball, time);
+        for wall in &walls {
+            accel += wall.collide_with(&ball);
+        }
+        for (j, other) in particles.iter().enumerate() {
+            if j != i {
+                accel += ball.accel_from_collision_with(&other);
+            }
+        }
+        accel
+    });
+}
@@ -0,0 +1,372 @@
+use super::Episode;
+use crate::{
+    actuators::Actuator,
+    linearization::Flatten,
+    physics::{self, Collider, Cuboid, ForceField, Gravity, Particle, Plane},
+    Model, Solid,
+};
+use cgmath::{prelude::*, Quaternion, Rad, Vector2, Vector3};
+
+#[derive(Clone)]
+pub struct BallOnPlateWorld {
+    /// Turns [`BallOnPlateSignals`] into the applied pitch and roll.
+    pub tilt_actuator: Actuator<Vector2<f32>>,
+
+    /// The position of the ball on the plate, in plate coordinates with the
+    /// origin at the center.
+    pub ball_pos: Vector2<f32>,
+    pub ball_vel: Vector2<f32>,
+    /// The ball once it has rolled off the plate, in world coordinates.
+    pub falling_ball: Option<Particle>,
+
+    /// Where the ball should be, in plate coordinates.
+    pub target: Vector2<f32>,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+impl BallOnPlateWorld {
+    /// The applied pitch and roll.
+    pub fn tilt(&self) -> Vector2<f32> {
+        self.tilt_actuator.output()
+    }
+    /// Rotates plate to world coordinates.
+    pub fn plate_orientation(&self) -> Quaternion<f32> {
+        let tilt = self.tilt();
+        Quaternion::from_angle_y(Rad(tilt.x)) * Quaternion::from_angle_x(Rad(tilt.y))
+    }
+    /// Where `point`, in plate coordinates on its top surface, is in the world.
+    pub fn plate_to_world(&self, point: Vector2<f32>) -> Vector3<f32> {
+        PLATE_CENTER + self.plate_orientation() * point.extend(PLATE_THICKNESS / 2.0)
+    }
+    /// The center of the ball, in world coordinates.
+    pub fn ball_world_pos(&self) -> Vector3<f32> {
+        self.falling_ball.map_or_else(
+            || {
+                PLATE_CENTER
+                    + self.plate_orientation()
+                        * self
+                            .ball_pos
+                            .extend(PLATE_THICKNESS / 2.0 + BallOnPlate::BALL_RADIUS)
+            },
+            |ball| ball.pos,
+        )
+    }
+    fn plate(&self) -> Cuboid {
+        Cuboid::new(
+            BallOnPlate::PLATE_SIZE.extend(PLATE_THICKNESS),
+            PLATE_CENTER,
+            self.plate_orientation(),
+        )
+    }
+}
+
+pub struct BallOnPlateSignals {
+    /// Rotation of the plate about the y axis, lowering its +x edge.
+    pub pitch: f32,
+    /// Rotation of the plate about the x axis, raising its +y edge.
+    pub roll: f32,
+}
+
+impl Flatten for BallOnPlateWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.tilt_actuator.flatten_into(values);
+        self.ball_pos.flatten_into(values);
+        self.ball_vel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.tilt_actuator.unflatten_from(values);
+        let values = self.ball_pos.unflatten_from(values);
+        self.ball_vel.unflatten_from(values)
+    }
+}
+
+impl Flatten for BallOnPlateSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.pitch.flatten_into(values);
+        self.roll.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.pitch.unflatten_from(values);
+        self.roll.unflatten_from(values)
+    }
+}
+
+/// Tilt a square plate to keep a ball rolling on it at its center.
+///
+/// The ball rolls without slipping, and falls off once its center passes an
+/// edge. Each tick on the plate costs the distance to the target, and rolling
+/// off costs [`BallOnPlate::FALL_PENALTY`]. The episode terminates when the
+/// ball hits the floor, and is truncated after [`BallOnPlate::MAX_STEPS`].
+pub struct BallOnPlate;
+
+/// A [`BallOnPlate`] where the target circles the center of the plate.
+pub struct BallOnPlateTracking;
+
+/// A [`BallOnPlate`] where the ball is kicked in random directions now and
+/// then.
+pub struct BallOnPlateDisturbed;
+
+impl BallOnPlate {
+    pub const PLATE_SIZE: Vector2<f32> = Vector2::new(1.0, 1.0);
+    pub const BALL_RADIUS: f32 = 0.04;
+    pub const MAX_TILT: f32 = 0.3;
+    /// The fastest the plate can change its tilt, in radians per second.
+    pub const MAX_TILT_RATE: f32 = 2.0;
+    pub const FALL_PENALTY: f32 = 100.0;
+    pub const MAX_STEPS: u32 = 2000;
+}
+
+impl BallOnPlateTracking {
+    pub const TARGET_RADIUS: f32 = 0.25;
+    /// The time the target takes to go around once.
+    pub const TARGET_PERIOD: f32 = 10.0;
+}
+
+impl BallOnPlateDisturbed {
+    /// The average time between kicks.
+    pub const KICK_INTERVAL: f32 = 2.0;
+    /// The largest change of velocity a kick causes.
+    pub const MAX_KICK: f32 = 0.3;
+}
+
+const PLATE_CENTER: Vector3<f32> = Vector3::new(0.0, 0.0, 1.0);
+const PLATE_THICKNESS: f32 = 0.04;
+const GRAVITY: Gravity = Gravity::EARTH;
+// A solid ball rolling without slipping accelerates at 5/7 of the rate it
+// would slide
+const ROLLING_FACTOR: f32 = 5.0 / 7.0;
+const ROLLING_DAMPING: f32 = 0.1;
+
+fn new_world() -> BallOnPlateWorld {
+    let initial = |size: f32| (fastrand::f32() - 0.5) * size / 2.0;
+    BallOnPlateWorld {
+        tilt_actuator: Actuator::ideal()
+            .with_saturation(BallOnPlate::MAX_TILT)
+            .with_slew_rate(BallOnPlate::MAX_TILT_RATE),
+        ball_pos: Vector2::new(
+            initial(BallOnPlate::PLATE_SIZE.x),
+            initial(BallOnPlate::PLATE_SIZE.y),
+        ),
+        ball_vel: Vector2::zero(),
+        falling_ball: None,
+        target: Vector2::zero(),
+        episode: Episode::default(),
+        time: 0.0,
+    }
+}
+
+// Advance any of the variants, which each start a new episode from their own
+// `new_world`
+fn update(
+    w: &mut BallOnPlateWorld,
+    signals: &BallOnPlateSignals,
+    new_world: fn() -> BallOnPlateWorld,
+) {
+    if w.episode.is_over() {
+        *w = BallOnPlateWorld {
+            episode: w.episode.next(),
+            time: w.time,
+            ..new_world()
+        };
+    }
+
+    let dt: f32 = physics::dt();
+    w.tilt_actuator
+        .update(Vector2::new(signals.pitch, signals.roll), dt);
+    let mut reward = 0.0;
+
+    let plate = w.plate();
+    if let Some(ball) = &mut w.falling_ball {
+        *ball = physics::time_step_with_rk4(&[*ball], &w.time, |particles, &time| {
+            particles
+                .iter()
+                .map(|ball| {
+                    plate.collide_with(ball)
+                        + Plane::FLOOR.collide_with(ball)
+                        + GRAVITY.accel(ball, time)
+                })
+                .collect()
+        })[0];
+    } else {
+        let gravity = w.plate_orientation().conjugate() * GRAVITY.accel;
+        let accel = gravity.truncate() * ROLLING_FACTOR - w.ball_vel * ROLLING_DAMPING;
+        w.ball_pos += w.ball_vel * dt + accel * (dt * dt / 2.0);
+        w.ball_vel += accel * dt;
+
+        let half_size = BallOnPlate::PLATE_SIZE / 2.0;
+        if w.ball_pos.x.abs() > half_size.x || w.ball_pos.y.abs() > half_size.y {
+            let vel = w.plate_orientation() * w.ball_vel.extend(0.0);
+            w.falling_ball = Some(Particle::new(
+                w.ball_world_pos(),
+                vel,
+                BallOnPlate::BALL_RADIUS,
+            ));
+            reward -= BallOnPlate::FALL_PENALTY;
+        } else {
+            reward -= (w.ball_pos - w.target).magnitude();
+        }
+    }
+    w.time += dt;
+
+    let landed = w
+        .falling_ball
+        .map_or(false, |ball| ball.pos.z <= ball.radius);
+    w.episode
+        .record(reward, landed, Some(BallOnPlate::MAX_STEPS));
+}
+
+impl Model for BallOnPlate {
+    type World = BallOnPlateWorld;
+    type Signals = BallOnPlateSignals;
+
+    fn new_world() -> Self::World {
+        new_world()
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            pitch: 0.0,
+            roll: 0.0,
+        }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        update(w, signals, Self::new_world);
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        solids(world)
+    }
+}
+
+impl Model for BallOnPlateTracking {
+    type World = BallOnPlateWorld;
+    type Signals = BallOnPlateSignals;
+
+    fn new_world() -> Self::World {
+        BallOnPlateWorld {
+            target: Vector2::unit_x() * Self::TARGET_RADIUS,
+            ..new_world()
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        BallOnPlate::new_signals()
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        update(w, signals, Self::new_world);
+        let angle =
+            physics::scalar::<f32>(w.episode.steps) * physics::dt::<f32>() / Self::TARGET_PERIOD;
+        let (sin, cos) = (angle * std::f32::consts::TAU).sin_cos();
+        w.target = Vector2::new(cos, sin) * Self::TARGET_RADIUS;
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        solids(world)
+    }
+}
+
+impl Model for BallOnPlateDisturbed {
+    type World = BallOnPlateWorld;
+    type Signals = BallOnPlateSignals;
+
+    fn new_world() -> Self::World {
+        new_world()
+    }
+    fn new_signals() -> Self::Signals {
+        BallOnPlate::new_signals()
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        update(w, signals, Self::new_world);
+        let kick_chance = physics::dt::<f32>() / Self::KICK_INTERVAL;
+        if w.falling_ball.is_none() && fastrand::f32() < kick_chance {
+            let direction = fastrand::f32() * std::f32::consts::TAU;
+            let (sin, cos) = direction.sin_cos();
+            w.ball_vel += Vector2::new(cos, sin) * (fastrand::f32() * Self::MAX_KICK);
+        }
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        solids(world)
+    }
+}
+
+fn solids(world: &BallOnPlateWorld) -> Vec<Solid> {
+    const PLATE_COLOR: Vector3<f32> = Vector3::new(0.7, 0.7, 0.7);
+    const STAND_COLOR: Vector3<f32> = Vector3::new(0.3, 0.3, 0.3);
+    const BALL_COLOR: Vector3<f32> = Vector3::new(0.5, 0.2, 0.3);
+    const TARGET_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+    const FLOOR_COLOR: Vector3<f32> = Vector3::new(0.9, 0.9, 0.9);
+
+    const STAND_RADIUS: f32 = 0.03;
+    const TARGET_RADIUS: f32 = 0.03;
+    const TARGET_THICKNESS: f32 = 0.004;
+
+    let up = world.plate_orientation() * Vector3::unit_z();
+    let target = world.plate_to_world(world.target);
+    let mut solids = vec![
+        Solid::new_cylinder(
+            PLATE_CENTER.truncate().extend(0.0),
+            PLATE_CENTER,
+            STAND_RADIUS,
+            STAND_COLOR,
+        ),
+        Solid::new_rectangular_cuboid(
+            BallOnPlate::PLATE_SIZE.extend(PLATE_THICKNESS),
+            PLATE_CENTER,
+            world.plate_orientation(),
+            PLATE_COLOR,
+        ),
+        Solid::new_cylinder(
+            target,
+            target + up * TARGET_THICKNESS,
+            TARGET_RADIUS,
+            TARGET_COLOR,
+        ),
+        Solid::new_sphere(world.ball_world_pos(), BallOnPlate::BALL_RADIUS, BALL_COLOR),
+    ];
+    solids.extend(Plane::FLOOR.solids(FLOOR_COLOR));
+    solids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracking_restarts_with_its_own_target() {
+        let mut world = BallOnPlateTracking::new_world();
+        world.episode.terminated = true;
+        BallOnPlateTracking::update(&mut world, &BallOnPlateTracking::new_signals());
+
+        // The first tick of the new episode is scored against the start of the
+        // circle, not the center
+        let start = Vector2::unit_x() * BallOnPlateTracking::TARGET_RADIUS;
+        let distance = (world.ball_pos - start).magnitude();
+        assert_eq!(world.episode.steps, 1);
+        assert!((world.episode.reward + distance).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ball_rolling_off_the_edge_falls_to_the_floor() {
+        let mut world = BallOnPlate::new_world();
+        world.ball_pos = Vector2::new(0.45, 0.0);
+        world.ball_vel = Vector2::new(1.0, 0.0);
+        let signals = BallOnPlate::new_signals();
+
+        while !world.episode.is_over() {
+            BallOnPlate::update(&mut world, &signals);
+            assert!(
+                world.episode.steps < 200,
+                "still falling at {:?}",
+                world.falling_ball
+            );
+        }
+        assert!(world.episode.terminated);
+        let ball = world.falling_ball.unwrap();
+        assert!(ball.pos.x > BallOnPlate::PLATE_SIZE.x / 2.0);
+        assert!(world.episode.total_reward < -BallOnPlate::FALL_PENALTY);
+    }
+}
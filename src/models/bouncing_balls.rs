@@ -1,13 +1,20 @@
 use crate::{
-    physics::{self, Particle, Plane},
+    physics::{
+        self, diagnostics::Diagnostics, Collider, ContactEvent, ForceField, Gravity, Particle,
+        ParticleSet, Plane, Rk4Scratch,
+    },
     Model, Solid,
 };
-use cgmath::{prelude::*, Quaternion, Vector3};
+use cgmath::Vector3;
 
 #[derive(Clone)]
 pub struct BouncingWorld {
     pub first: Particle,
     pub second: Particle,
+    /// Contacts during the last tick. The balls are particles 0 and 1, the
+    /// floor is collider 0.
+    pub contacts: Vec<ContactEvent>,
+    pub time: f32,
 }
 
 pub struct BouncingSignals {
@@ -17,6 +24,9 @@ pub struct BouncingSignals {
 pub struct BouncingBalls;
 
 const RADIUS: f32 = 0.3;
+const GRAVITY: Gravity = Gravity {
+    accel: Vector3::new(0.0, 0.0, -4.0),
+};
 
 impl Model for BouncingBalls {
     type World = BouncingWorld;
@@ -34,6 +44,8 @@ impl Model for BouncingBalls {
                 -0.5f32 * Vector3::unit_y(),
                 RADIUS,
             ),
+            contacts: Vec::new(),
+            time: 0.0,
         }
     }
     fn new_signals() -> Self::Signals {
@@ -41,41 +53,51 @@ impl Model for BouncingBalls {
     }
 
     fn update(world: &mut Self::World, _signals: &Self::Signals) {
-        let mut new = physics::time_step_with_rk4(&[world.first, world.second], &(), accels);
-        new = physics::time_step_with_rk4(&new, &(), accels);
-        new = physics::time_step_with_rk4(&new, &(), accels);
-        new = physics::time_step_with_rk4(&new, &(), accels);
-        new = physics::time_step_with_rk4(&new, &(), accels);
-
-        world.first = new[0];
-        world.second = new[1];
-
-        fn accels(particles: &[Particle], _: &()) -> Vec<Vector3<f32>> {
-            const GRAVITY: f32 = 4.0;
-
-            if let &[first, second] = particles {
-                vec![
-                    Plane::FLOOR.collide_with(&first) - GRAVITY * Vector3::unit_z(),
-                    Plane::FLOOR.collide_with(&second) - GRAVITY * Vector3::unit_z(),
-                ]
-            } else {
-                unreachable!()
-            }
+        // The buffers are made per tick rather than kept in the world, which
+        // the runner clones every visible tick
+        let particles = &mut ParticleSet::from_particles(&[world.first, world.second]);
+        let mut scratch = Rk4Scratch::new();
+        world.contacts.clear();
+        for _ in 0..5 {
+            physics::step_with_rk4(
+                particles,
+                &mut scratch,
+                &world.time,
+                |particles, &time, accels| {
+                    for (accel, ball) in accels.iter_mut().zip(particles.iter()) {
+                        *accel = Plane::FLOOR.collide_with(&ball) + GRAVITY.accel(&ball, time);
+                    }
+                },
+            );
+            world.time += physics::dt::<f32>();
+            world.contacts.extend(
+                particles
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, ball)| Plane::FLOOR.contact_event(0, &ball, i)),
+            );
         }
+
+        world.first = particles.get(0);
+        world.second = particles.get(1);
     }
 
     fn get_solids(world: &Self::World) -> Vec<Solid> {
         const COLOR: Vector3<f32> = Vector3::new(0.5, 0.5, 0.2);
         const GROUND_COLOR: Vector3<f32> = Vector3::new(0.9, 0.9, 0.9);
-        vec![
+        let mut solids = vec![
             Solid::new_sphere(world.first.pos, RADIUS, COLOR),
             Solid::new_sphere(world.second.pos, RADIUS, COLOR),
-            Solid::new_rectangular_cuboid(
-                Vector3::new(10.0, 10.0, 0.1),
-                Vector3::unit_z() * (-0.05),
-                Quaternion::one(),
-                GROUND_COLOR,
-            ),
-        ]
+        ];
+        solids.extend(Plane::FLOOR.solids(GROUND_COLOR));
+        solids
+    }
+
+    fn diagnostics(world: &Self::World) -> Option<Diagnostics> {
+        Some(Diagnostics::measure(
+            &[world.first, world.second],
+            GRAVITY.accel,
+            [],
+        ))
     }
 }
@@ -0,0 +1,226 @@
+use super::Episode;
+use crate::{linearization::Flatten, Model, Solid};
+use cgmath::{prelude::*, Quaternion, Vector3};
+
+#[derive(Clone)]
+pub struct CartPoleWorld {
+    /// The position of the cart along the rail.
+    pub x: f32,
+    pub x_vel: f32,
+    /// The angle of the pole from upright, positive towards +x.
+    pub theta: f32,
+    pub theta_vel: f32,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+pub struct CartPoleSignals {
+    /// The force pushing the cart along the rail, limited to
+    /// [`CartPole::FORCE_MAG`] in either direction.
+    pub force: f32,
+}
+
+impl CartPoleSignals {
+    /// Push with full force to the left or right, as with the discrete actions
+    /// of the reference.
+    pub fn push(&mut self, right: bool) {
+        self.force = if right {
+            CartPole::FORCE_MAG
+        } else {
+            -CartPole::FORCE_MAG
+        };
+    }
+}
+
+impl Flatten for CartPoleWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.x.flatten_into(values);
+        self.x_vel.flatten_into(values);
+        self.theta.flatten_into(values);
+        self.theta_vel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.x.unflatten_from(values);
+        let values = self.x_vel.unflatten_from(values);
+        let values = self.theta.unflatten_from(values);
+        self.theta_vel.unflatten_from(values)
+    }
+}
+
+impl Flatten for CartPoleSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.force.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        self.force.unflatten_from(values)
+    }
+}
+
+/// The classic cart-pole balancing task, with the constants, dynamics and
+/// Euler integration of the widely used Gym `CartPole-v1`, so that results
+/// are comparable with published baselines.
+///
+/// Every tick earns a reward of 1, including the one that terminates the
+/// episode by letting the pole fall past [`CartPole::THETA_THRESHOLD`] or the
+/// cart leave [`CartPole::X_THRESHOLD`]. Episodes are truncated after
+/// [`CartPole::MAX_STEPS`].
+pub struct CartPole;
+
+impl CartPole {
+    pub const GRAVITY: f32 = 9.8;
+    pub const CART_MASS: f32 = 1.0;
+    pub const POLE_MASS: f32 = 0.1;
+    /// Half the length of the pole.
+    pub const HALF_LENGTH: f32 = 0.5;
+    pub const FORCE_MAG: f32 = 10.0;
+    /// The time per tick.
+    pub const TAU: f32 = 0.02;
+    /// 12 degrees.
+    pub const THETA_THRESHOLD: f32 = 12.0 * 2.0 * std::f32::consts::PI / 360.0;
+    pub const X_THRESHOLD: f32 = 2.4;
+    pub const MAX_STEPS: u32 = 500;
+}
+
+impl Model for CartPole {
+    type World = CartPoleWorld;
+    type Signals = CartPoleSignals;
+
+    fn new_world() -> Self::World {
+        let initial = || (fastrand::f32() - 0.5) / 10.0;
+        Self::World {
+            x: initial(),
+            x_vel: initial(),
+            theta: initial(),
+            theta_vel: initial(),
+            episode: Episode::default(),
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals { force: 0.0 }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        let force = signals.force.clamp(-Self::FORCE_MAG, Self::FORCE_MAG);
+        let total_mass = Self::CART_MASS + Self::POLE_MASS;
+        let pole_mass_length = Self::POLE_MASS * Self::HALF_LENGTH;
+        let (sin, cos) = w.theta.sin_cos();
+
+        let temp = (force + pole_mass_length * w.theta_vel * w.theta_vel * sin) / total_mass;
+        let theta_accel = (Self::GRAVITY * sin - cos * temp)
+            / (Self::HALF_LENGTH * (4.0 / 3.0 - Self::POLE_MASS * cos * cos / total_mass));
+        let x_accel = temp - pole_mass_length * theta_accel * cos / total_mass;
+
+        w.x += Self::TAU * w.x_vel;
+        w.x_vel += Self::TAU * x_accel;
+        w.theta += Self::TAU * w.theta_vel;
+        w.theta_vel += Self::TAU * theta_accel;
+        w.time += Self::TAU;
+
+        let terminated = w.x.abs() > Self::X_THRESHOLD || w.theta.abs() > Self::THETA_THRESHOLD;
+        w.episode.record(1.0, terminated, Some(Self::MAX_STEPS));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const RAIL_COLOR: Vector3<f32> = Vector3::new(0.7, 0.7, 0.7);
+        const CART_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+        const POLE_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
+        const FAILED_COLOR: Vector3<f32> = Vector3::new(0.8, 0.1, 0.1);
+
+        const RAIL_RADIUS: f32 = 0.02;
+        const CART_SIZE: Vector3<f32> = Vector3::new(0.5, 0.3, 0.3);
+        const POLE_RADIUS: f32 = 0.04;
+
+        let cart = Vector3::new(world.x, 0.0, 0.0);
+        let tip = cart
+            + Vector3::new(world.theta.sin(), 0.0, world.theta.cos())
+                * (2.0 * CartPole::HALF_LENGTH);
+        let pole_color = if world.episode.terminated {
+            FAILED_COLOR
+        } else {
+            POLE_COLOR
+        };
+        vec![
+            Solid::new_cylinder(
+                Vector3::new(-CartPole::X_THRESHOLD, 0.0, 0.0),
+                Vector3::new(CartPole::X_THRESHOLD, 0.0, 0.0),
+                RAIL_RADIUS,
+                RAIL_COLOR,
+            ),
+            Solid::new_rectangular_cuboid(CART_SIZE, cart, Quaternion::one(), CART_COLOR),
+            Solid::new_cylinder(cart, tip, POLE_RADIUS, pole_color),
+            Solid::new_sphere(tip, POLE_RADIUS * 2.0, pole_color),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn constants_match_the_reference() {
+        assert_close(CartPole::GRAVITY, 9.8);
+        assert_close(CartPole::CART_MASS, 1.0);
+        assert_close(CartPole::POLE_MASS, 0.1);
+        assert_close(CartPole::HALF_LENGTH, 0.5);
+        assert_close(CartPole::FORCE_MAG, 10.0);
+        assert_close(CartPole::TAU, 0.02);
+        assert_close(CartPole::THETA_THRESHOLD, 0.209_439_5);
+        assert_close(CartPole::X_THRESHOLD, 2.4);
+        assert_eq!(CartPole::MAX_STEPS, 500);
+    }
+
+    #[test]
+    fn a_push_from_rest_matches_the_reference() {
+        let mut world = CartPoleWorld {
+            x: 0.0,
+            x_vel: 0.0,
+            theta: 0.0,
+            theta_vel: 0.0,
+            ..CartPole::new_world()
+        };
+        let mut signals = CartPole::new_signals();
+        signals.push(true);
+        CartPole::update(&mut world, &signals);
+
+        // The state after stepping the reference from rest with action 1
+        let expected = [0.0, 0.195_121_96, 0.0, -0.292_682_93];
+        let actual = [world.x, world.x_vel, world.theta, world.theta_vel];
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            assert_close(actual, expected);
+        }
+        assert_close(world.episode.reward, 1.0);
+        assert!(!world.episode.is_over());
+    }
+
+    #[test]
+    fn falling_terminates_and_the_next_update_resets() {
+        let mut world = CartPoleWorld {
+            theta: CartPole::THETA_THRESHOLD,
+            theta_vel: 1.0,
+            ..CartPole::new_world()
+        };
+        CartPole::update(&mut world, &CartPole::new_signals());
+        assert!(world.episode.terminated);
+        assert_close(world.episode.total_reward, 1.0);
+
+        CartPole::update(&mut world, &CartPole::new_signals());
+        assert!(!world.episode.is_over());
+        assert_eq!((world.episode.steps, world.episode.completed), (1, 1));
+        assert!(world.theta.abs() < 0.1);
+    }
+}
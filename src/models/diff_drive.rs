@@ -0,0 +1,327 @@
+use super::Episode;
+use crate::{
+    actuators::Actuator,
+    linearization::Flatten,
+    physics::{self, Collider, Cuboid, Plane},
+    Model, Solid,
+};
+use cgmath::{prelude::*, Quaternion, Rad, Vector1, Vector2, Vector3};
+use std::f32::consts::TAU;
+
+#[derive(Clone)]
+pub struct DiffDriveWorld {
+    /// The point midway between the wheels, on the floor.
+    pub pos: Vector2<f32>,
+    /// The direction the robot faces, counterclockwise from +x.
+    pub heading: f32,
+    /// Turn [`DiffDriveSignals`] into applied wheel speeds, left then right.
+    pub wheels: [Actuator<Vector1<f32>>; 2],
+
+    /// The walls around the arena and the obstacles within it.
+    pub walls: Vec<Cuboid>,
+    pub goal: Vector2<f32>,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+impl DiffDriveWorld {
+    /// The speed each wheel currently applies, left then right.
+    pub fn wheel_speeds(&self) -> [f32; 2] {
+        self.wheels.map(|wheel| wheel.output().x)
+    }
+    /// The unit vector the robot faces.
+    pub fn forward(&self) -> Vector2<f32> {
+        let (sin, cos) = self.heading.sin_cos();
+        Vector2::new(cos, sin)
+    }
+    /// The distance to the nearest wall along each of the
+    /// [`DiffDrive::LIDAR_RAYS`] rays, or [`DiffDrive::LIDAR_RANGE`] where
+    /// nothing is in range. Ray `i` points [`DiffDrive::lidar_angle`]`(i)`
+    /// counterclockwise from the heading.
+    pub fn lidar_scan(&self) -> Vec<f32> {
+        let origin = self.pos.extend(LIDAR_HEIGHT);
+        (0..DiffDrive::LIDAR_RAYS)
+            .map(|i| {
+                let (sin, cos) = (self.heading + DiffDrive::lidar_angle(i)).sin_cos();
+                let direction = Vector3::new(cos, sin, 0.0);
+                self.walls
+                    .iter()
+                    .filter_map(|wall| wall.ray_cast(origin, direction, DiffDrive::LIDAR_RANGE))
+                    .fold(DiffDrive::LIDAR_RANGE, f32::min)
+            })
+            .collect()
+    }
+    /// Whether the robot's body overlaps a wall.
+    pub fn is_colliding(&self) -> bool {
+        !is_clear(&self.walls, self.pos, DiffDrive::ROBOT_RADIUS)
+    }
+}
+
+pub struct DiffDriveSignals {
+    /// The speed of the rim of the left wheel, limited to
+    /// [`DiffDrive::MAX_WHEEL_SPEED`] in either direction.
+    pub left_wheel_speed: f32,
+    pub right_wheel_speed: f32,
+}
+
+/// The pose and wheel states.
+impl Flatten for DiffDriveWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.pos.flatten_into(values);
+        self.heading.flatten_into(values);
+        for wheel in &self.wheels {
+            wheel.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.pos.unflatten_from(values);
+        let mut values = self.heading.unflatten_from(values);
+        for wheel in &mut self.wheels {
+            values = wheel.unflatten_from(values);
+        }
+        values
+    }
+}
+
+impl Flatten for DiffDriveSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.left_wheel_speed.flatten_into(values);
+        self.right_wheel_speed.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.left_wheel_speed.unflatten_from(values);
+        self.right_wheel_speed.unflatten_from(values)
+    }
+}
+
+/// A two-wheeled robot driving to a goal in a walled arena with random
+/// obstacles, seeing them only through a planar lidar.
+///
+/// The wheels roll without slipping, so the robot moves along its heading at
+/// their mean speed, and turns at their difference over
+/// [`DiffDrive::WHEEL_BASE`]. Each tick costs the distance to the goal.
+/// Reaching the goal earns [`DiffDrive::GOAL_BONUS`], hitting a wall costs
+/// [`DiffDrive::COLLISION_PENALTY`], and both terminate the episode. Episodes
+/// are truncated after [`DiffDrive::MAX_STEPS`].
+pub struct DiffDrive;
+
+impl DiffDrive {
+    /// The distance between the wheels.
+    pub const WHEEL_BASE: f32 = 0.3;
+    pub const WHEEL_RADIUS: f32 = 0.05;
+    /// The radius of the round body, which must stay clear of the walls.
+    pub const ROBOT_RADIUS: f32 = 0.2;
+    pub const MAX_WHEEL_SPEED: f32 = 1.0;
+    /// The fastest a wheel can change its speed.
+    pub const MAX_WHEEL_ACCEL: f32 = 4.0;
+
+    pub const LIDAR_RAYS: usize = 36;
+    pub const LIDAR_RANGE: f32 = 4.0;
+
+    /// The side length of the square inside the walls, centered on the origin.
+    pub const ARENA_SIZE: f32 = 8.0;
+    pub const OBSTACLE_COUNT: usize = 5;
+    /// How close the robot must come to the goal to reach it.
+    pub const GOAL_TOLERANCE: f32 = 0.3;
+    pub const GOAL_BONUS: f32 = 100.0;
+    pub const COLLISION_PENALTY: f32 = 100.0;
+    pub const MAX_STEPS: u32 = 3000;
+
+    /// The angle of lidar ray `i`, counterclockwise from the heading.
+    #[must_use]
+    pub fn lidar_angle(i: usize) -> f32 {
+        physics::scalar::<f32>(i) * TAU / physics::scalar::<f32>(Self::LIDAR_RAYS)
+    }
+}
+
+const WALL_THICKNESS: f32 = 0.2;
+const WALL_HEIGHT: f32 = 0.4;
+const OBSTACLE_SIZE: [f32; 2] = [0.3, 1.5];
+const LIDAR_HEIGHT: f32 = 0.25;
+
+// The walls around the arena, then randomly placed and turned boxes
+fn new_walls() -> Vec<Cuboid> {
+    let half_size = DiffDrive::ARENA_SIZE / 2.0;
+    let length = DiffDrive::ARENA_SIZE + 2.0 * WALL_THICKNESS;
+    let offset = half_size + WALL_THICKNESS / 2.0;
+    let mut walls: Vec<Cuboid> = [
+        (Vector2::unit_x(), Vector2::new(WALL_THICKNESS, length)),
+        (-Vector2::unit_x(), Vector2::new(WALL_THICKNESS, length)),
+        (Vector2::unit_y(), Vector2::new(length, WALL_THICKNESS)),
+        (-Vector2::unit_y(), Vector2::new(length, WALL_THICKNESS)),
+    ]
+    .into_iter()
+    .map(|(side, dimensions)| {
+        Cuboid::new(
+            dimensions.extend(WALL_HEIGHT),
+            (side * offset).extend(WALL_HEIGHT / 2.0),
+            Quaternion::one(),
+        )
+    })
+    .collect();
+
+    let [min_size, max_size] = OBSTACLE_SIZE;
+    let size = || min_size + fastrand::f32() * (max_size - min_size);
+    for _ in 0..DiffDrive::OBSTACLE_COUNT {
+        let center = random_point(half_size - max_size / 2.0);
+        walls.push(Cuboid::new(
+            Vector3::new(size(), size(), WALL_HEIGHT),
+            center.extend(WALL_HEIGHT / 2.0),
+            Quaternion::from_angle_z(Rad(fastrand::f32() * TAU)),
+        ));
+    }
+    walls
+}
+
+// Uniform in the square of the given half size about the origin
+fn random_point(half_size: f32) -> Vector2<f32> {
+    let coordinate = || (fastrand::f32() * 2.0 - 1.0) * half_size;
+    Vector2::new(coordinate(), coordinate())
+}
+
+fn is_clear(walls: &[Cuboid], point: Vector2<f32>, clearance: f32) -> bool {
+    let point = point.extend(WALL_HEIGHT / 2.0);
+    walls
+        .iter()
+        .all(|wall| wall.surface_near(point).0 > clearance)
+}
+
+// A point with room for the robot to turn around
+fn random_clear_point(walls: &[Cuboid]) -> Vector2<f32> {
+    let half_size = DiffDrive::ARENA_SIZE / 2.0;
+    loop {
+        let point = random_point(half_size);
+        if is_clear(walls, point, 2.0 * DiffDrive::ROBOT_RADIUS) {
+            return point;
+        }
+    }
+}
+
+impl Model for DiffDrive {
+    type World = DiffDriveWorld;
+    type Signals = DiffDriveSignals;
+
+    fn new_world() -> Self::World {
+        let walls = new_walls();
+        let pos = random_clear_point(&walls);
+        let goal = loop {
+            let goal = random_clear_point(&walls);
+            if goal.distance(pos) > Self::ARENA_SIZE / 4.0 {
+                break goal;
+            }
+        };
+        let wheel = Actuator::ideal()
+            .with_saturation(Self::MAX_WHEEL_SPEED)
+            .with_slew_rate(Self::MAX_WHEEL_ACCEL);
+        Self::World {
+            pos,
+            heading: fastrand::f32() * TAU,
+            wheels: [wheel; 2],
+            walls,
+            goal,
+            episode: Episode::default(),
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            left_wheel_speed: 0.0,
+            right_wheel_speed: 0.0,
+        }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        let dt: f32 = physics::dt();
+        let commands = [signals.left_wheel_speed, signals.right_wheel_speed];
+        for (wheel, command) in w.wheels.iter_mut().zip(commands) {
+            wheel.update(Vector1::new(command), dt);
+        }
+        let [left, right] = w.wheel_speeds();
+        let speed = (left + right) / 2.0;
+        let turn_rate = (right - left) / Self::WHEEL_BASE;
+        // Move along the heading halfway through the turn
+        w.heading += turn_rate * dt / 2.0;
+        w.pos += w.forward() * (speed * dt);
+        w.heading += turn_rate * dt / 2.0;
+        w.heading = w.heading.rem_euclid(TAU);
+        w.time += dt;
+
+        let distance = w.pos.distance(w.goal);
+        let (reward, terminated) = if w.is_colliding() {
+            (-Self::COLLISION_PENALTY, true)
+        } else if distance < Self::GOAL_TOLERANCE {
+            (Self::GOAL_BONUS, true)
+        } else {
+            (-distance, false)
+        };
+        w.episode.record(reward, terminated, Some(Self::MAX_STEPS));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const WALL_COLOR: Vector3<f32> = Vector3::new(0.6, 0.6, 0.6);
+        const BODY_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
+        const WHEEL_COLOR: Vector3<f32> = Vector3::new(0.2, 0.2, 0.2);
+        const NOSE_COLOR: Vector3<f32> = Vector3::new(0.9, 0.6, 0.1);
+        const LIDAR_COLOR: Vector3<f32> = Vector3::new(0.5, 0.2, 0.3);
+        const GOAL_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+        const FLOOR_COLOR: Vector3<f32> = Vector3::new(0.9, 0.9, 0.9);
+
+        const BODY_HEIGHT: f32 = 0.1;
+        const WHEEL_WIDTH: f32 = 0.03;
+        const LIDAR_RADIUS: f32 = 0.05;
+        const HIT_RADIUS: f32 = 0.02;
+        const GOAL_THICKNESS: f32 = 0.01;
+
+        let forward = world.forward().extend(0.0);
+        let left = Vector3::new(-forward.y, forward.x, 0.0);
+        let base = world.pos.extend(DiffDrive::WHEEL_RADIUS);
+        let top = base + Vector3::unit_z() * BODY_HEIGHT;
+        let lidar = world.pos.extend(LIDAR_HEIGHT);
+
+        let mut solids = vec![
+            Solid::new_cylinder(base, top, DiffDrive::ROBOT_RADIUS, BODY_COLOR),
+            Solid::new_sphere(
+                top + forward * (DiffDrive::ROBOT_RADIUS * 0.8),
+                LIDAR_RADIUS / 2.0,
+                NOSE_COLOR,
+            ),
+            Solid::new_cylinder(top, lidar, LIDAR_RADIUS, LIDAR_COLOR),
+            Solid::new_cylinder(
+                world.goal.extend(0.0),
+                world.goal.extend(GOAL_THICKNESS),
+                DiffDrive::GOAL_TOLERANCE,
+                GOAL_COLOR,
+            ),
+        ];
+        for side in [1.0, -1.0] {
+            let center = base + left * (side * DiffDrive::WHEEL_BASE / 2.0);
+            solids.push(Solid::new_cylinder(
+                center - left * (WHEEL_WIDTH / 2.0),
+                center + left * (WHEEL_WIDTH / 2.0),
+                DiffDrive::WHEEL_RADIUS,
+                WHEEL_COLOR,
+            ));
+        }
+        for (i, range) in world.lidar_scan().into_iter().enumerate() {
+            if range < DiffDrive::LIDAR_RANGE {
+                let (sin, cos) = (world.heading + DiffDrive::lidar_angle(i)).sin_cos();
+                let hit = lidar + Vector3::new(cos, sin, 0.0) * range;
+                solids.push(Solid::new_sphere(hit, HIT_RADIUS, LIDAR_COLOR));
+            }
+        }
+        for wall in &world.walls {
+            solids.extend(wall.solids(WALL_COLOR));
+        }
+        solids.extend(Plane::FLOOR.solids(FLOOR_COLOR));
+        solids
+    }
+}
@@ -0,0 +1,99 @@
+/// The bookkeeping of an episodic task, kept in the world of models that
+/// define a reward.
+///
+/// An episode ends when the task reaches a terminal state or runs out of
+/// steps. The world stays as it ended for one tick, so that the controller can
+/// see the final state and reward, and the model then starts a new episode on
+/// its next update.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Episode {
+    /// The reward earned by the last tick.
+    pub reward: f32,
+    /// The reward earned so far this episode.
+    pub total_reward: f32,
+    /// Ticks since the episode began.
+    pub steps: u32,
+    /// Whether the last tick reached a terminal state, by failing or
+    /// completing the task.
+    pub terminated: bool,
+    /// Whether the last tick ran out of steps without terminating.
+    pub truncated: bool,
+    /// The number of episodes that have ended.
+    pub completed: u32,
+}
+
+impl Episode {
+    /// Whether the episode has ended, so the next update starts a new one.
+    #[must_use]
+    pub fn is_over(&self) -> bool {
+        self.terminated || self.truncated
+    }
+
+    /// Account for a tick that earned `reward`. Episodes are truncated after
+    /// `max_steps`, if given.
+    pub(crate) fn record(&mut self, reward: f32, terminated: bool, max_steps: Option<u32>) {
+        self.reward = reward;
+        self.total_reward += reward;
+        self.steps += 1;
+        self.terminated = terminated;
+        self.truncated = !terminated && max_steps.map_or(false, |max| self.steps >= max);
+        if self.is_over() {
+            self.completed += 1;
+        }
+    }
+
+    /// The bookkeeping for the next episode, carrying over the count of
+    /// completed ones.
+    pub(crate) fn next(&self) -> Self {
+        Self {
+            completed: self.completed,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn termination_ends_an_episode() {
+        let mut episode = Episode::default();
+        episode.record(1.0, false, None);
+        assert!(!episode.is_over());
+        episode.record(2.0, true, None);
+        assert!(episode.terminated && !episode.truncated && episode.is_over());
+        assert_eq!((episode.steps, episode.completed), (2, 1));
+        assert_close(episode.total_reward, 3.0);
+
+        let next = episode.next();
+        assert!(!next.is_over());
+        assert_eq!((next.steps, next.completed), (0, 1));
+        assert_close(next.total_reward, 0.0);
+    }
+
+    #[test]
+    fn episodes_are_truncated_after_max_steps() {
+        let mut episode = Episode::default();
+        for _ in 0..2 {
+            episode.record(1.0, false, Some(3));
+            assert!(!episode.is_over());
+        }
+        episode.record(1.0, false, Some(3));
+        assert!(episode.truncated && !episode.terminated);
+        assert_eq!(episode.completed, 1);
+    }
+
+    #[test]
+    fn terminating_on_the_last_step_is_not_truncation() {
+        let mut episode = Episode::default();
+        episode.record(1.0, false, Some(2));
+        episode.record(1.0, true, Some(2));
+        assert!(episode.terminated && !episode.truncated);
+        assert_eq!(episode.completed, 1);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    physics::{
+        self, Collider, ContactMaterial, Cuboid, ForceField, Gravity, Particle, PenaltyContact,
+        SphFluid,
+    },
+    Model, Solid,
+};
+use cgmath::{prelude::*, Quaternion, Vector2, Vector3};
+
+#[derive(Clone)]
+pub struct FluidTankWorld {
+    /// The fluid, in the frame of the tank.
+    pub particles: Vec<Particle>,
+    pub time: f32,
+}
+
+pub struct FluidTankSignals {
+    /// The horizontal acceleration of the tank, which the fluid feels as an
+    /// inertial force in the opposite direction.
+    pub tank_accel: Vector2<f32>,
+}
+
+/// A tank of water, released as a dam break against one end. Accelerate the
+/// tank to make it slosh.
+pub struct FluidTank;
+
+// The inside of the tank, with the floor at z = 0
+const TANK_SIZE: Vector3<f32> = Vector3::new(1.6, 0.8, 1.2);
+const WALL_THICKNESS: f32 = 0.1;
+// The initial block of water, in particles
+const WATER_BLOCK: [usize; 3] = [6, 6, 8];
+const SPACING: f32 = 0.1;
+const STIFFNESS: f32 = 40.0;
+const VISCOSITY: f32 = 10.0;
+const GRAVITY: Gravity = Gravity {
+    accel: Vector3::new(0.0, 0.0, -2.0),
+};
+const STEPS_PER_TICK: usize = 4;
+// Water slides along walls, and barely bounces off them
+const WALL_CONTACT: PenaltyContact = PenaltyContact {
+    stiffness: 5000.0,
+    material: ContactMaterial {
+        static_friction: 0.0,
+        kinetic_friction: 0.0,
+        restitution: 0.2,
+    },
+};
+
+impl FluidTank {
+    fn fluid() -> SphFluid {
+        SphFluid::new(SPACING, STIFFNESS, VISCOSITY)
+    }
+
+    // The floor and four walls
+    fn walls() -> [Cuboid; 5] {
+        let (size, t) = (TANK_SIZE, WALL_THICKNESS);
+        let wall = |dimensions: Vector3<f32>, center: Vector3<f32>| {
+            Cuboid::new(dimensions, center, Quaternion::one()).with_contact(WALL_CONTACT)
+        };
+        let outer = size + Vector3::new(2.0 * t, 2.0 * t, 0.0);
+        [
+            wall(
+                Vector3::new(outer.x, outer.y, t),
+                Vector3::new(0.0, 0.0, -t / 2.0),
+            ),
+            wall(
+                Vector3::new(t, outer.y, size.z),
+                Vector3::new(-size.x / 2.0 - t / 2.0, 0.0, size.z / 2.0),
+            ),
+            wall(
+                Vector3::new(t, outer.y, size.z),
+                Vector3::new(size.x / 2.0 + t / 2.0, 0.0, size.z / 2.0),
+            ),
+            wall(
+                Vector3::new(size.x, t, size.z),
+                Vector3::new(0.0, -size.y / 2.0 - t / 2.0, size.z / 2.0),
+            ),
+            wall(
+                Vector3::new(size.x, t, size.z),
+                Vector3::new(0.0, size.y / 2.0 + t / 2.0, size.z / 2.0),
+            ),
+        ]
+    }
+}
+
+impl Model for FluidTank {
+    type World = FluidTankWorld;
+    type Signals = FluidTankSignals;
+
+    fn new_world() -> Self::World {
+        let radius = Self::fluid().particle_radius();
+        // Against the -x wall, with a particle radius to spare from the walls
+        let corner = Vector3::new(-TANK_SIZE.x / 2.0, -TANK_SIZE.y / 2.0, 0.0)
+            + Vector3::new(radius, radius, radius);
+        let [nx, ny, nz] = WATER_BLOCK;
+        let mut particles = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let offset = Vector3::new(x, y, z).map(physics::scalar::<f32>) * SPACING;
+                    particles.push(Particle::new(corner + offset, Vector3::zero(), radius));
+                }
+            }
+        }
+        Self::World {
+            particles,
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            tank_accel: Vector2::zero(),
+        }
+    }
+
+    fn update(world: &mut Self::World, signals: &Self::Signals) {
+        let fluid = Self::fluid();
+        let walls = Self::walls();
+        let inertial = -signals.tank_accel.extend(0.0);
+        for _ in 0..STEPS_PER_TICK {
+            world.particles =
+                physics::time_step_with_rk4(&world.particles, &world.time, |particles, &time| {
+                    let mut accels = fluid.accels(particles);
+                    for (accel, particle) in accels.iter_mut().zip(particles) {
+                        *accel += walls
+                            .iter()
+                            .map(|wall| wall.collide_with(particle))
+                            .sum::<Vector3<f32>>()
+                            + GRAVITY.accel(particle, time)
+                            + inertial;
+                    }
+                    accels
+                });
+            world.time += physics::dt::<f32>();
+        }
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const WATER_COLOR: Vector3<f32> = Vector3::new(0.1, 0.3, 0.8);
+        const TANK_COLOR: Vector3<f32> = Vector3::new(0.7, 0.7, 0.7);
+
+        let mut solids: Vec<Solid> = world
+            .particles
+            .iter()
+            .map(|particle| Solid::new_sphere(particle.pos, particle.radius, WATER_COLOR))
+            .collect();
+        // Leave out the near wall, so the water can be seen
+        for wall in &Self::walls()[..4] {
+            solids.extend(wall.solids(TANK_COLOR));
+        }
+        solids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn water_stays_in_a_still_tank() {
+        let mut world = FluidTank::new_world();
+        let signals = FluidTank::new_signals();
+        assert_eq!(world.particles.len(), 288);
+        for _ in 0..50 {
+            FluidTank::update(&mut world, &signals);
+        }
+        let half_size = TANK_SIZE.truncate() / 2.0;
+        for particle in &world.particles {
+            let pos = particle.pos;
+            assert!(
+                pos.x.abs() < half_size.x && pos.y.abs() < half_size.y && pos.z > 0.0,
+                "escaped to {pos:?}",
+            );
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use super::inverted_n_pendulum::{self as chain, NODE_RADIUS};
 use crate::{
-    physics::{self, Particle, Spring},
+    actuators::Actuator,
+    linearization::Flatten,
+    physics::{self, diagnostics::Diagnostics, Body, ContactEvent, Particle},
     Model, Solid,
 };
 use cgmath::{prelude::*, Vector2, Vector3};
@@ -8,42 +11,112 @@ use cgmath::{prelude::*, Vector2, Vector3};
 pub struct IDPWorld {
     pub base_pos: Vector2<f32>,
     pub base_vel: Vector2<f32>,
+    /// Turns [`IDPSignals::base_accel`] into the applied base acceleration.
+    /// Ideal, except in an [`InvertedDoublePendulumLimited`].
+    pub base_actuator: Actuator<Vector2<f32>>,
 
     pub mid_pos: Vector3<f32>,
     pub mid_vel: Vector3<f32>,
     pub top_pos: Vector3<f32>,
     pub top_vel: Vector3<f32>,
+
+    /// Contacts between nodes at the end of the last tick. The base, middle
+    /// and top nodes are particles 0, 1 and 2.
+    pub contacts: Vec<ContactEvent>,
+    pub time: f32,
+}
+
+impl IDPWorld {
+    /// Whether the pendulum has folded so far that the top node touches the
+    /// base.
+    pub fn top_touches_base(&self) -> bool {
+        self.contacts
+            .iter()
+            .any(|contact| contact.is_between(Body::Particle(2), Body::Particle(0)))
+    }
 }
 
 pub struct IDPSignals {
     pub base_accel: Vector2<f32>,
 }
 
+/// The base, node and actuator states.
+impl Flatten for IDPWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.base_pos.flatten_into(values);
+        self.base_vel.flatten_into(values);
+        self.base_actuator.flatten_into(values);
+        self.mid_pos.flatten_into(values);
+        self.mid_vel.flatten_into(values);
+        self.top_pos.flatten_into(values);
+        self.top_vel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.base_pos.unflatten_from(values);
+        let values = self.base_vel.unflatten_from(values);
+        let values = self.base_actuator.unflatten_from(values);
+        let values = self.mid_pos.unflatten_from(values);
+        let values = self.mid_vel.unflatten_from(values);
+        let values = self.top_pos.unflatten_from(values);
+        self.top_vel.unflatten_from(values)
+    }
+}
+
+impl Flatten for IDPSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.base_accel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        self.base_accel.unflatten_from(values)
+    }
+}
+
+/// Two rods standing on a base that moves in the xy plane. Keep them upright
+/// by accelerating the base.
+///
+/// This is an [`InvertedNPendulum`] of two rods, whose world names the middle
+/// and top nodes.
+///
+/// [`InvertedNPendulum`]: super::InvertedNPendulum
 pub struct InvertedDoublePendulum;
 
-const NODE_RADIUS: f32 = 0.15;
+/// An [`InvertedDoublePendulum`] whose base is driven by a motor of limited
+/// strength and speed.
+pub struct InvertedDoublePendulumLimited;
+
+impl InvertedDoublePendulumLimited {
+    pub const MAX_BASE_ACCEL: f32 = 2.0;
+    pub const MAX_BASE_JERK: f32 = 40.0;
+    /// The time constant of the motor.
+    pub const BASE_LAG: f32 = 0.02;
+
+    pub(super) fn base_actuator() -> Actuator<Vector2<f32>> {
+        Actuator::ideal()
+            .with_saturation(Self::MAX_BASE_ACCEL)
+            .with_slew_rate(Self::MAX_BASE_JERK)
+            .with_lag(Self::BASE_LAG)
+    }
+}
 
 impl Model for InvertedDoublePendulum {
     type World = IDPWorld;
     type Signals = IDPSignals;
 
     fn new_world() -> Self::World {
-        let disturbance = || {
-            Vector3::new(
-                fastrand::f32() / 20.0,
-                fastrand::f32() / 20.0,
-                fastrand::f32() / 20.0,
-            )
-        };
+        let [mid_pos, top_pos] = chain::initial_nodes();
         Self::World {
             base_pos: Zero::zero(),
             base_vel: Zero::zero(),
+            base_actuator: Actuator::ideal(),
 
-            mid_pos: (Vector3::unit_z() + disturbance()).normalize(),
+            mid_pos,
             mid_vel: Vector3::zero(),
 
-            top_pos: Vector3::unit_z() * 2.0 + disturbance(),
+            top_pos,
             top_vel: Vector3::zero(),
+
+            contacts: Vec::new(),
+            time: 0.0,
         }
     }
     fn new_signals() -> Self::Signals {
@@ -53,12 +126,11 @@ impl Model for InvertedDoublePendulum {
     }
 
     fn update(w: &mut Self::World, signals: &Self::Signals) {
-        let particles = [
-            Particle::new(w.base_pos.extend(0.0), w.base_vel.extend(0.0), NODE_RADIUS),
-            Particle::new(w.mid_pos, w.mid_vel, NODE_RADIUS),
-            Particle::new(w.top_pos, w.top_vel, NODE_RADIUS),
-        ];
-        let new = physics::time_step_with_rk4(&particles, signals, idp_accels);
+        let base_accel = w
+            .base_actuator
+            .update(signals.base_accel, physics::dt::<f32>());
+        let new = physics::time_step_with_rk4(&particles(w), &(base_accel, w.time), chain::accels);
+        w.time += physics::dt::<f32>();
 
         w.base_pos = new[0].pos.truncate();
         w.base_vel = new[0].vel.truncate();
@@ -67,49 +139,49 @@ impl Model for InvertedDoublePendulum {
         w.top_pos = new[2].pos;
         w.top_vel = new[2].vel;
 
-        fn idp_accels(particles: &[Particle], signals: &IDPSignals) -> Vec<Vector3<f32>> {
-            const GRAVITY_ACCEL: f32 = 0.3;
-
-            if let [base, mid, top] = particles {
-                vec![
-                    // Base
-                    signals.base_accel.extend(0.0),
-                    // Mid
-                    mid.accel_from_spring_to(top, Spring::UNIT_ROD)
-                        + mid.accel_from_spring_to(base, Spring::UNIT_ROD)
-                        + mid.accel_from_collision_with(top)
-                        + mid.accel_from_collision_with(base)
-                        - Vector3::unit_z() * GRAVITY_ACCEL,
-                    // Top
-                    top.accel_from_spring_to(mid, Spring::UNIT_ROD)
-                        + top.accel_from_collision_with(mid)
-                        + top.accel_from_collision_with(base)
-                        - Vector3::unit_z() * GRAVITY_ACCEL,
-                ]
-            } else {
-                unreachable!()
-            }
+        w.contacts = chain::contacts(&particles(w));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        chain::solids(&particles(world))
+    }
+
+    fn diagnostics(world: &Self::World) -> Option<Diagnostics> {
+        Some(chain::diagnostics(&particles(world)))
+    }
+}
+
+impl Model for InvertedDoublePendulumLimited {
+    type World = IDPWorld;
+    type Signals = IDPSignals;
+
+    fn new_world() -> Self::World {
+        Self::World {
+            base_actuator: Self::base_actuator(),
+            ..InvertedDoublePendulum::new_world()
         }
     }
+    fn new_signals() -> Self::Signals {
+        InvertedDoublePendulum::new_signals()
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        InvertedDoublePendulum::update(w, signals);
+    }
 
     fn get_solids(world: &Self::World) -> Vec<Solid> {
-        const CONTROL_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
-        const NODE_COLOR: Vector3<f32> = Vector3::new(0.5, 0.2, 0.3);
-        const ROD_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
-
-        const ROD_RADIUS: f32 = 0.1;
-
-        vec![
-            Solid::new_sphere(world.base_pos.extend(0.0), NODE_RADIUS, CONTROL_COLOR),
-            Solid::new_sphere(world.mid_pos, NODE_RADIUS, NODE_COLOR),
-            Solid::new_sphere(world.top_pos, NODE_RADIUS, NODE_COLOR),
-            Solid::new_cylinder(
-                world.base_pos.extend(0.0),
-                world.mid_pos,
-                ROD_RADIUS,
-                ROD_COLOR,
-            ),
-            Solid::new_cylinder(world.mid_pos, world.top_pos, ROD_RADIUS, ROD_COLOR),
-        ]
+        InvertedDoublePendulum::get_solids(world)
     }
+
+    fn diagnostics(world: &Self::World) -> Option<Diagnostics> {
+        InvertedDoublePendulum::diagnostics(world)
+    }
+}
+
+fn particles(w: &IDPWorld) -> [Particle; 3] {
+    [
+        Particle::new(w.base_pos.extend(0.0), w.base_vel.extend(0.0), NODE_RADIUS),
+        Particle::new(w.mid_pos, w.mid_vel, NODE_RADIUS),
+        Particle::new(w.top_pos, w.top_vel, NODE_RADIUS),
+    ]
 }
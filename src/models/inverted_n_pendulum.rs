@@ -0,0 +1,306 @@
+use super::InvertedDoublePendulumLimited;
+use crate::{
+    actuators::Actuator,
+    linearization::Flatten,
+    physics::{
+        self, diagnostics::Diagnostics, Body, ContactEvent, ForceField, Gravity, Particle, Spring,
+    },
+    Model, Solid,
+};
+use cgmath::{prelude::*, Vector2, Vector3};
+
+#[derive(Clone)]
+pub struct InvertedNPendulumWorld<const N: usize> {
+    pub base_pos: Vector2<f32>,
+    pub base_vel: Vector2<f32>,
+    /// Turns [`InvertedNPendulumSignals::base_accel`] into the applied base
+    /// acceleration.
+    pub base_actuator: Actuator<Vector2<f32>>,
+
+    /// The nodes above the base, from the bottom up.
+    pub node_pos: [Vector3<f32>; N],
+    pub node_vel: [Vector3<f32>; N],
+
+    /// Contacts between nodes at the end of the last tick. The base is
+    /// particle 0, and the nodes follow it from the bottom up.
+    pub contacts: Vec<ContactEvent>,
+    pub time: f32,
+}
+
+impl<const N: usize> InvertedNPendulumWorld<N> {
+    /// The top node, at the end of the chain.
+    pub fn top_pos(&self) -> Vector3<f32> {
+        self.node_pos[N - 1]
+    }
+    /// Whether the pendulum has folded so far that a node other than the
+    /// first touches the base.
+    pub fn touches_base(&self) -> bool {
+        (2..=N).any(|i| {
+            self.contacts
+                .iter()
+                .any(|contact| contact.is_between(Body::Particle(i), Body::Particle(0)))
+        })
+    }
+}
+
+pub struct InvertedNPendulumSignals {
+    pub base_accel: Vector2<f32>,
+}
+
+/// The base, actuator and node states.
+impl<const N: usize> Flatten for InvertedNPendulumWorld<N> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.base_pos.flatten_into(values);
+        self.base_vel.flatten_into(values);
+        self.base_actuator.flatten_into(values);
+        for (pos, vel) in self.node_pos.iter().zip(&self.node_vel) {
+            pos.flatten_into(values);
+            vel.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.base_pos.unflatten_from(values);
+        let values = self.base_vel.unflatten_from(values);
+        let mut values = self.base_actuator.unflatten_from(values);
+        for (pos, vel) in self.node_pos.iter_mut().zip(&mut self.node_vel) {
+            values = pos.unflatten_from(values);
+            values = vel.unflatten_from(values);
+        }
+        values
+    }
+}
+
+impl Flatten for InvertedNPendulumSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.base_accel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        self.base_accel.unflatten_from(values)
+    }
+}
+
+/// A chain of `N` rods standing on a base that moves in the xy plane. Keep it
+/// upright by accelerating the base.
+///
+/// With two rods, this moves and looks just like [`InvertedDoublePendulum`],
+/// whose world names the nodes instead of numbering them.
+///
+/// [`InvertedDoublePendulum`]: super::InvertedDoublePendulum
+pub struct InvertedNPendulum<const N: usize>;
+
+pub type InvertedSinglePendulum = InvertedNPendulum<1>;
+pub type InvertedTriplePendulum = InvertedNPendulum<3>;
+
+/// An [`InvertedNPendulum`] whose base is driven by the motor of
+/// [`InvertedDoublePendulumLimited`].
+///
+/// [`InvertedDoublePendulumLimited`]: super::InvertedDoublePendulumLimited
+pub struct InvertedNPendulumLimited<const N: usize>;
+
+impl<const N: usize> InvertedNPendulum<N> {
+    // Refuses to build a pendulum without rods, once `new_world` is used
+    const HAS_RODS: () = assert!(N > 0, "an inverted pendulum needs at least one rod");
+}
+
+pub(super) const NODE_RADIUS: f32 = 0.15;
+const GRAVITY: Gravity = Gravity {
+    accel: Vector3::new(0.0, 0.0, -0.3),
+};
+
+impl<const N: usize> Model for InvertedNPendulum<N> {
+    type World = InvertedNPendulumWorld<N>;
+    type Signals = InvertedNPendulumSignals;
+
+    fn new_world() -> Self::World {
+        let () = Self::HAS_RODS;
+        Self::World {
+            base_pos: Zero::zero(),
+            base_vel: Zero::zero(),
+            base_actuator: Actuator::ideal(),
+
+            node_pos: initial_nodes(),
+            node_vel: [Vector3::zero(); N],
+
+            contacts: Vec::new(),
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            base_accel: Zero::zero(),
+        }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        let base_accel = w
+            .base_actuator
+            .update(signals.base_accel, physics::dt::<f32>());
+        let new = physics::time_step_with_rk4(&particles(w), &(base_accel, w.time), accels);
+        w.time += physics::dt::<f32>();
+
+        w.base_pos = new[0].pos.truncate();
+        w.base_vel = new[0].vel.truncate();
+        for (i, node) in new[1..].iter().enumerate() {
+            w.node_pos[i] = node.pos;
+            w.node_vel[i] = node.vel;
+        }
+
+        w.contacts = contacts(&particles(w));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        solids(&particles(world))
+    }
+
+    fn diagnostics(world: &Self::World) -> Option<Diagnostics> {
+        Some(diagnostics(&particles(world)))
+    }
+}
+
+impl<const N: usize> Model for InvertedNPendulumLimited<N> {
+    type World = InvertedNPendulumWorld<N>;
+    type Signals = InvertedNPendulumSignals;
+
+    fn new_world() -> Self::World {
+        Self::World {
+            base_actuator: InvertedDoublePendulumLimited::base_actuator(),
+            ..InvertedNPendulum::new_world()
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        InvertedNPendulum::<N>::new_signals()
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        InvertedNPendulum::update(w, signals);
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        InvertedNPendulum::get_solids(world)
+    }
+
+    fn diagnostics(world: &Self::World) -> Option<Diagnostics> {
+        InvertedNPendulum::diagnostics(world)
+    }
+}
+
+/// The base, then the nodes from the bottom up.
+fn particles<const N: usize>(w: &InvertedNPendulumWorld<N>) -> Vec<Particle> {
+    let base = Particle::new(w.base_pos.extend(0.0), w.base_vel.extend(0.0), NODE_RADIUS);
+    let nodes = (w.node_pos.iter().zip(&w.node_vel))
+        .map(|(&pos, &vel)| Particle::new(pos, vel, NODE_RADIUS));
+    std::iter::once(base).chain(nodes).collect()
+}
+
+// The chain leaning a little off upright, with every rod at its rest length
+pub(super) fn initial_nodes<const N: usize>() -> [Vector3<f32>; N] {
+    let disturbance = || {
+        Vector3::new(
+            fastrand::f32() / 20.0,
+            fastrand::f32() / 20.0,
+            fastrand::f32() / 20.0,
+        )
+    };
+    let mut below = Vector3::zero();
+    std::array::from_fn(|_| {
+        below += (Vector3::unit_z() + disturbance()).normalize() * Spring::UNIT_ROD.rest_length;
+        below
+    })
+}
+
+// Each node hangs between its neighbours in the chain, and bumps into
+// every other node
+pub(super) fn accels(
+    particles: &[Particle],
+    &(base_accel, time): &(Vector2<f32>, f32),
+) -> Vec<Vector3<f32>> {
+    let mut accels = vec![base_accel.extend(0.0)];
+    for (i, node) in particles.iter().enumerate().skip(1) {
+        let mut accel = node.accel_from_spring_to(&particles[i - 1], Spring::UNIT_ROD)
+            + GRAVITY.accel(node, time);
+        if let Some(above) = particles.get(i + 1) {
+            accel += node.accel_from_spring_to(above, Spring::UNIT_ROD);
+        }
+        for (j, other) in particles.iter().enumerate() {
+            if j != i {
+                accel += node.accel_from_collision_with(other);
+            }
+        }
+        accels.push(accel);
+    }
+    accels
+}
+
+// Every pair of touching particles in the chain
+pub(super) fn contacts(particles: &[Particle]) -> Vec<ContactEvent> {
+    particles
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| {
+            particles[..i]
+                .iter()
+                .enumerate()
+                .filter_map(move |(j, b)| a.collision_event_with(i, b, j))
+        })
+        .collect()
+}
+
+pub(super) fn solids(particles: &[Particle]) -> Vec<Solid> {
+    const CONTROL_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+    const NODE_COLOR: Vector3<f32> = Vector3::new(0.5, 0.2, 0.3);
+    const ROD_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
+
+    const ROD_RADIUS: f32 = 0.1;
+
+    let base = particles[0].pos;
+    let mut solids = vec![Solid::new_sphere(base, NODE_RADIUS, CONTROL_COLOR)];
+    let mut below = base;
+    for node in &particles[1..] {
+        solids.push(Solid::new_sphere(node.pos, NODE_RADIUS, NODE_COLOR));
+        solids.push(Solid::new_cylinder(below, node.pos, ROD_RADIUS, ROD_COLOR));
+        below = node.pos;
+    }
+    solids
+}
+
+pub(super) fn diagnostics(particles: &[Particle]) -> Diagnostics {
+    Diagnostics::measure(
+        particles,
+        GRAVITY.accel,
+        (1..particles.len()).map(|i| (i - 1, i, Spring::UNIT_ROD)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{inverted_double_pendulum::IDPSignals, InvertedDoublePendulum};
+
+    #[test]
+    fn rods_start_at_rest_length() {
+        let world = InvertedTriplePendulum::new_world();
+        let mut below = Vector3::zero();
+        for node in world.node_pos {
+            assert!(((node - below).magnitude() - Spring::UNIT_ROD.rest_length).abs() < 1e-6);
+            assert!(node.z > below.z);
+            below = node;
+        }
+    }
+
+    #[test]
+    fn two_rods_match_the_double_pendulum() {
+        let mut double = InvertedDoublePendulum::new_world();
+        let mut chain = InvertedNPendulumWorld {
+            node_pos: [double.mid_pos, double.top_pos],
+            ..InvertedNPendulum::<2>::new_world()
+        };
+        let base_accel = Vector2::new(0.5, -0.2);
+        for _ in 0..200 {
+            InvertedDoublePendulum::update(&mut double, &IDPSignals { base_accel });
+            InvertedNPendulum::update(&mut chain, &InvertedNPendulumSignals { base_accel });
+        }
+        assert_eq!(chain.node_pos, [double.mid_pos, double.top_pos]);
+        assert_eq!(chain.node_vel, [double.mid_vel, double.top_vel]);
+        assert_eq!(chain.contacts.len(), double.contacts.len());
+    }
+}
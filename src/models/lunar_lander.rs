@@ -0,0 +1,412 @@
+use super::Episode;
+use crate::{
+    actuators::Actuator,
+    linearization::Flatten,
+    physics::{
+        self, Collider, ContactMaterial, Cuboid, Gravity, PenaltyContact, RigidBody, Wrench,
+    },
+    Model, Solid,
+};
+use cgmath::{prelude::*, Quaternion, Rad, Vector1, Vector2, Vector3};
+use std::f32::consts::TAU;
+
+#[derive(Clone)]
+pub struct LunarLanderWorld {
+    /// The lander, whose mass includes the fuel left.
+    pub body: RigidBody,
+    /// Turns [`LunarLanderSignals::main_throttle`] into the applied throttle.
+    pub main_engine: Actuator<Vector1<f32>>,
+    /// The applied attitude commands, which are zero once the fuel runs out.
+    pub attitude: Vector3<f32>,
+    pub fuel: f32,
+
+    pub pad: Cuboid,
+    /// The ground and the rocks on it.
+    pub terrain: Vec<Cuboid>,
+    /// Whether the episode ended with a successful landing on the pad.
+    pub landed: bool,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+impl LunarLanderWorld {
+    /// The throttle of the main engine, between 0 and 1, which is zero once
+    /// the fuel runs out.
+    pub fn main_throttle(&self) -> f32 {
+        if self.fuel > 0.0 {
+            self.main_engine.output().x
+        } else {
+            0.0
+        }
+    }
+    /// The angle between the lander's axis and the vertical.
+    pub fn tilt(&self) -> f32 {
+        (self.body.orientation * Vector3::unit_z())
+            .z
+            .clamp(-1.0, 1.0)
+            .acos()
+    }
+    /// Where each foot is, in world coordinates.
+    pub fn feet(&self) -> [Vector3<f32>; 4] {
+        LunarLander::foot_offsets().map(|offset| self.body.to_world(offset))
+    }
+    /// The landing pad and the terrain.
+    pub fn colliders(&self) -> impl Iterator<Item = &Cuboid> {
+        std::iter::once(&self.pad).chain(&self.terrain)
+    }
+}
+
+pub struct LunarLanderSignals {
+    /// The commanded throttle of the main engine, between 0 and 1. The engine
+    /// pushes along the lander's z axis.
+    pub main_throttle: f32,
+    /// The commanded torque about each of the lander's axes, between -1 and 1
+    /// as a fraction of [`LunarLander::MAX_ATTITUDE_TORQUE`].
+    pub attitude: Vector3<f32>,
+}
+
+/// The body, engine and fuel states.
+impl Flatten for LunarLanderWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.body.flatten_into(values);
+        self.main_engine.flatten_into(values);
+        self.fuel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.body.unflatten_from(values);
+        let values = self.main_engine.unflatten_from(values);
+        self.fuel.unflatten_from(values)
+    }
+}
+
+impl Flatten for LunarLanderSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.main_throttle.flatten_into(values);
+        self.attitude.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.main_throttle.unflatten_from(values);
+        self.attitude.unflatten_from(values)
+    }
+}
+
+/// Land on the pad at the origin from high above it, with limited fuel for
+/// the main engine and attitude thrusters.
+///
+/// The episode terminates when a foot or the hull first touches anything. The
+/// lander has landed if it touches down on the pad no faster than
+/// [`LunarLander::MAX_LANDING_SPEED`] and tilted no more than
+/// [`LunarLander::MAX_LANDING_TILT`], which earns
+/// [`LunarLander::LANDING_BONUS`]. Any other touchdown, or straying beyond
+/// [`LunarLander::ARENA_RADIUS`], is a crash and costs
+/// [`LunarLander::CRASH_PENALTY`]. Meanwhile each second costs the distance to
+/// the pad, and each unit of fuel burned costs [`LunarLander::FUEL_COST`].
+/// Episodes are truncated after [`LunarLander::MAX_STEPS`].
+pub struct LunarLander;
+
+impl LunarLander {
+    /// The mass without fuel.
+    pub const DRY_MASS: f32 = 10.0;
+    pub const INERTIA: Vector3<f32> = Vector3::new(8.0, 8.0, 10.0);
+    pub const INITIAL_FUEL: f32 = 5.0;
+    pub const MAX_MAIN_THRUST: f32 = 40.0;
+    pub const MAIN_ENGINE_LAG: f32 = 0.1;
+    pub const MAX_ATTITUDE_TORQUE: f32 = 4.0;
+    /// Fuel burned per second by the main engine at full throttle.
+    pub const MAIN_BURN_RATE: f32 = 0.1;
+    /// Fuel burned per second by the thrusters for each axis at full torque.
+    pub const ATTITUDE_BURN_RATE: f32 = 0.02;
+
+    /// The width and depth of the pad, whose top is at
+    /// [`LunarLander::PAD_HEIGHT`] above the origin.
+    pub const PAD_SIZE: Vector2<f32> = Vector2::new(4.0, 4.0);
+    pub const PAD_HEIGHT: f32 = 0.2;
+    pub const START_HEIGHT: f32 = 30.0;
+
+    pub const MAX_LANDING_SPEED: f32 = 2.0;
+    pub const MAX_LANDING_TILT: f32 = 0.2;
+    pub const LANDING_BONUS: f32 = 100.0;
+    pub const CRASH_PENALTY: f32 = 100.0;
+    pub const FUEL_COST: f32 = 10.0;
+    /// How far the lander may stray from the pad horizontally before the
+    /// episode terminates.
+    pub const ARENA_RADIUS: f32 = 50.0;
+    pub const MAX_STEPS: u32 = 6000;
+
+    /// Where each foot is, in body coordinates.
+    #[must_use]
+    pub fn foot_offsets() -> [Vector3<f32>; 4] {
+        [
+            Vector2::new(1.0, 1.0),
+            Vector2::new(-1.0, 1.0),
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+        ]
+        .map(|corner| (corner * LEG_SPREAD).extend(-LEG_DROP))
+    }
+}
+
+const GRAVITY: Gravity = Gravity::MOON;
+// How far each foot is from the center of mass, sideways along both
+// horizontal axes and down
+const LEG_SPREAD: f32 = 1.2;
+const LEG_DROP: f32 = 1.2;
+const HULL_SIZE: Vector3<f32> = Vector3::new(1.6, 1.6, 1.4);
+const LEG_CONTACT: PenaltyContact = PenaltyContact {
+    stiffness: 2000.0,
+    material: ContactMaterial {
+        static_friction: 0.8,
+        kinetic_friction: 0.6,
+        restitution: 0.1,
+    },
+};
+const GROUND_SIZE: f32 = 2.0 * LunarLander::ARENA_RADIUS;
+const GROUND_THICKNESS: f32 = 1.0;
+const ROCK_COUNT: usize = 40;
+const ROCK_SIZE: [f32; 2] = [0.5, 3.0];
+// Rocks keep this far from the center of the pad
+const ROCK_CLEARANCE: f32 = 5.0;
+
+// Each pair of attitude thrusters, drawn at these body positions and pushing
+// in these directions for a positive torque about each axis
+const THRUSTER_PAIRS: [[(Vector3<f32>, Vector3<f32>); 2]; 3] = {
+    let (x, y, z) = (HULL_SIZE.x / 2.0, HULL_SIZE.y / 2.0, HULL_SIZE.z / 2.0);
+    [
+        [
+            (Vector3::new(0.0, y, z), Vector3::new(0.0, 0.0, 1.0)),
+            (Vector3::new(0.0, -y, z), Vector3::new(0.0, 0.0, -1.0)),
+        ],
+        [
+            (Vector3::new(x, 0.0, z), Vector3::new(0.0, 0.0, -1.0)),
+            (Vector3::new(-x, 0.0, z), Vector3::new(0.0, 0.0, 1.0)),
+        ],
+        [
+            (Vector3::new(x, 0.0, z), Vector3::new(0.0, 1.0, 0.0)),
+            (Vector3::new(-x, 0.0, z), Vector3::new(0.0, -1.0, 0.0)),
+        ],
+    ]
+};
+
+fn new_pad() -> Cuboid {
+    Cuboid::new(
+        LunarLander::PAD_SIZE.extend(LunarLander::PAD_HEIGHT),
+        Vector3::unit_z() * (LunarLander::PAD_HEIGHT / 2.0),
+        Quaternion::one(),
+    )
+    .with_contact(LEG_CONTACT)
+}
+
+fn new_terrain() -> Vec<Cuboid> {
+    let ground = Cuboid::new(
+        Vector3::new(GROUND_SIZE, GROUND_SIZE, GROUND_THICKNESS),
+        Vector3::unit_z() * (-GROUND_THICKNESS / 2.0),
+        Quaternion::one(),
+    );
+    let mut terrain = vec![ground.with_contact(LEG_CONTACT)];
+
+    let [min_size, max_size] = ROCK_SIZE;
+    let size = || min_size + fastrand::f32() * (max_size - min_size);
+    for _ in 0..ROCK_COUNT {
+        let (sin, cos) = (fastrand::f32() * TAU).sin_cos();
+        let distance =
+            ROCK_CLEARANCE + fastrand::f32().sqrt() * (LunarLander::ARENA_RADIUS - ROCK_CLEARANCE);
+        let center = Vector2::new(cos, sin) * distance;
+        // Half buried, so they make bumps of various heights
+        let rock = Cuboid::new(
+            Vector3::new(size(), size(), size()),
+            center.extend(0.0),
+            Quaternion::from_angle_z(Rad(fastrand::f32() * TAU)),
+        );
+        terrain.push(rock.with_contact(LEG_CONTACT));
+    }
+    terrain
+}
+
+fn wrench(body: &RigidBody, colliders: &[&Cuboid], thrust: f32, attitude: Vector3<f32>) -> Wrench {
+    let up = body.orientation * Vector3::unit_z();
+    let engines = Wrench::force(up * thrust)
+        + Wrench::torque(body.orientation * (attitude * LunarLander::MAX_ATTITUDE_TORQUE));
+
+    // Each leg carries a quarter of the mass
+    let legs: Wrench = LunarLander::foot_offsets()
+        .into_iter()
+        .map(|offset| {
+            let foot = body.to_world(offset);
+            let vel = body.velocity_at(foot);
+            let accel: Vector3<f32> = colliders
+                .iter()
+                .map(|collider| {
+                    let (gap, normal) = collider.surface_near(foot);
+                    collider.contact().accel(gap, normal, vel)
+                })
+                .sum();
+            Wrench::at_offset(accel * (body.mass / 4.0), foot - body.pos)
+        })
+        .sum();
+
+    engines + legs + Wrench::force(GRAVITY.accel * body.mass)
+}
+
+impl Model for LunarLander {
+    type World = LunarLanderWorld;
+    type Signals = LunarLanderSignals;
+
+    fn new_world() -> Self::World {
+        let random = |scale: f32| (fastrand::f32() * 2.0 - 1.0) * scale;
+        let mut body = RigidBody::new(
+            Vector3::new(random(10.0), random(10.0), Self::START_HEIGHT),
+            Self::DRY_MASS + Self::INITIAL_FUEL,
+            Self::INERTIA,
+        );
+        body.vel = Vector3::new(random(2.0), random(2.0), -2.0);
+        body.orientation =
+            Quaternion::from_angle_x(Rad(random(0.1))) * Quaternion::from_angle_y(Rad(random(0.1)));
+        Self::World {
+            body,
+            main_engine: Actuator::ideal()
+                .with_saturation(1.0)
+                .with_lag(Self::MAIN_ENGINE_LAG),
+            attitude: Vector3::zero(),
+            fuel: Self::INITIAL_FUEL,
+            pad: new_pad(),
+            terrain: new_terrain(),
+            landed: false,
+            episode: Episode::default(),
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            main_throttle: 0.0,
+            attitude: Vector3::zero(),
+        }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        let dt: f32 = physics::dt();
+        w.main_engine
+            .update(Vector1::new(signals.main_throttle.max(0.0)), dt);
+        w.attitude = if w.fuel > 0.0 {
+            signals.attitude.map(|torque| torque.clamp(-1.0, 1.0))
+        } else {
+            Vector3::zero()
+        };
+        let throttle = w.main_throttle();
+        let burned = (throttle * Self::MAIN_BURN_RATE
+            + w.attitude.map(f32::abs).sum() * Self::ATTITUDE_BURN_RATE)
+            * dt;
+        let burned = burned.min(w.fuel);
+        w.fuel -= burned;
+
+        let colliders: Vec<&Cuboid> = w.colliders().collect();
+        let (thrust, attitude) = (throttle * Self::MAX_MAIN_THRUST, w.attitude);
+        w.body = w
+            .body
+            .step_with_rk4(|body| wrench(body, &colliders, thrust, attitude));
+        w.body.mass = Self::DRY_MASS + w.fuel;
+        w.time += dt;
+
+        let touching = |point: Vector3<f32>, clearance: f32| {
+            w.colliders()
+                .any(|collider| collider.surface_near(point).0 <= clearance)
+        };
+        let feet = w.feet();
+        let touched_down =
+            feet.iter().any(|&foot| touching(foot, 0.0)) || touching(w.body.pos, HULL_SIZE.z / 2.0);
+        let on_pad = feet.iter().all(|&foot| {
+            (foot.x.abs() < Self::PAD_SIZE.x / 2.0) && (foot.y.abs() < Self::PAD_SIZE.y / 2.0)
+        });
+        w.landed = touched_down
+            && on_pad
+            && w.body.vel.magnitude() <= Self::MAX_LANDING_SPEED
+            && w.tilt() <= Self::MAX_LANDING_TILT;
+        let crashed =
+            (touched_down && !w.landed) || w.body.pos.truncate().magnitude() > Self::ARENA_RADIUS;
+
+        let pad_top = Vector3::unit_z() * Self::PAD_HEIGHT;
+        let mut reward = -(w.body.pos - pad_top).magnitude() * dt - burned * Self::FUEL_COST;
+        if w.landed {
+            reward += Self::LANDING_BONUS;
+        } else if crashed {
+            reward -= Self::CRASH_PENALTY;
+        }
+        w.episode
+            .record(reward, w.landed || crashed, Some(Self::MAX_STEPS));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const HULL_COLOR: Vector3<f32> = Vector3::new(0.8, 0.7, 0.3);
+        const LEG_COLOR: Vector3<f32> = Vector3::new(0.5, 0.5, 0.5);
+        const NOZZLE_COLOR: Vector3<f32> = Vector3::new(0.2, 0.2, 0.25);
+        const FLAME_COLOR: Vector3<f32> = Vector3::new(1.0, 0.5, 0.1);
+        const PUFF_COLOR: Vector3<f32> = Vector3::new(0.6, 0.8, 1.0);
+        const PAD_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+        const TERRAIN_COLOR: Vector3<f32> = Vector3::new(0.6, 0.6, 0.6);
+
+        const LEG_RADIUS: f32 = 0.05;
+        const FOOT_RADIUS: f32 = 0.15;
+        const NOZZLE_RADIUS: f32 = 0.3;
+        const NOZZLE_LENGTH: f32 = 0.3;
+        const MAX_FLAME_LENGTH: f32 = 2.0;
+        const PUFF_RADIUS: f32 = 0.06;
+        const MAX_PUFF_LENGTH: f32 = 0.5;
+
+        let body = &world.body;
+        let down = body.orientation * -Vector3::unit_z();
+        let nozzle = body.to_world(-Vector3::unit_z() * (HULL_SIZE.z / 2.0));
+        let nozzle_end = nozzle + down * NOZZLE_LENGTH;
+        let mut solids = vec![
+            Solid::new_rectangular_cuboid(HULL_SIZE, body.pos, body.orientation, HULL_COLOR),
+            Solid::new_cylinder(nozzle, nozzle_end, NOZZLE_RADIUS, NOZZLE_COLOR),
+        ];
+        for (foot, offset) in world.feet().into_iter().zip(LunarLander::foot_offsets()) {
+            let hip = body.to_world(offset.truncate().extend(0.0) * 0.5);
+            solids.extend([
+                Solid::new_cylinder(hip, foot, LEG_RADIUS, LEG_COLOR),
+                Solid::new_sphere(foot, FOOT_RADIUS, LEG_COLOR),
+            ]);
+        }
+
+        let throttle = world.main_throttle();
+        if throttle > 0.0 {
+            solids.push(Solid::new_cylinder(
+                nozzle_end,
+                nozzle_end + down * (throttle * MAX_FLAME_LENGTH),
+                NOZZLE_RADIUS * 0.8,
+                FLAME_COLOR,
+            ));
+        }
+        // Each thruster blows out against the direction it pushes
+        let commands: [f32; 3] = world.attitude.into();
+        for (pair, command) in THRUSTER_PAIRS.iter().zip(commands) {
+            if command == 0.0 {
+                continue;
+            }
+            for &(offset, push) in pair {
+                let thruster = body.to_world(offset);
+                let exhaust = body.orientation * push * -command * MAX_PUFF_LENGTH;
+                solids.push(Solid::new_cylinder(
+                    thruster,
+                    thruster + exhaust,
+                    PUFF_RADIUS,
+                    PUFF_COLOR,
+                ));
+            }
+        }
+
+        solids.extend(world.pad.solids(PAD_COLOR));
+        for collider in &world.terrain {
+            solids.extend(collider.solids(TERRAIN_COLOR));
+        }
+        solids
+    }
+}
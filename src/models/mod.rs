@@ -1,9 +1,37 @@
 //! Premade simulation environments. Pick one to run!
 
+mod acrobot;
+mod ball_arena;
+mod ball_on_plate;
 mod bouncing_balls;
+mod cart_pole;
+mod diff_drive;
+mod episode;
+mod fluid_tank;
 mod inverted_double_pendulum;
+mod inverted_n_pendulum;
+mod lunar_lander;
+mod pendulum;
+mod pursuit_evasion;
+mod quadrotor;
+mod robot_arm;
 mod simple;
 
+pub use acrobot::Acrobot;
+pub use ball_arena::BallArena;
+pub use ball_on_plate::{BallOnPlate, BallOnPlateDisturbed, BallOnPlateTracking};
 pub use bouncing_balls::BouncingBalls;
-pub use inverted_double_pendulum::InvertedDoublePendulum;
-pub use simple::SimpleModel;
+pub use cart_pole::CartPole;
+pub use diff_drive::DiffDrive;
+pub use episode::Episode;
+pub use fluid_tank::FluidTank;
+pub use inverted_double_pendulum::{InvertedDoublePendulum, InvertedDoublePendulumLimited};
+pub use inverted_n_pendulum::{
+    InvertedNPendulum, InvertedNPendulumLimited, InvertedSinglePendulum, InvertedTriplePendulum,
+};
+pub use lunar_lander::LunarLander;
+pub use pendulum::Pendulum;
+pub use pursuit_evasion::{PursuitEvasion, PursuitObservation, Role, Sighting};
+pub use quadrotor::{Quadrotor, QuadrotorWaypoints};
+pub use robot_arm::{RobotArm, ThreeLinkArm, TwoLinkArm};
+pub use simple::{SimpleModel, SimpleModelLimited};
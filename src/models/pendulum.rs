@@ -0,0 +1,192 @@
+use super::Episode;
+use crate::{linearization::Flatten, Model, Solid};
+use cgmath::Vector3;
+use std::f32::consts::PI;
+
+#[derive(Clone)]
+pub struct PendulumWorld {
+    /// The angle of the link from upright, positive towards +x.
+    pub theta: f32,
+    pub theta_vel: f32,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+pub struct PendulumSignals {
+    /// The torque at the pivot, limited to [`Pendulum::MAX_TORQUE`] in either
+    /// direction.
+    pub torque: f32,
+}
+
+impl Flatten for PendulumWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.theta.flatten_into(values);
+        self.theta_vel.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.theta.unflatten_from(values);
+        self.theta_vel.unflatten_from(values)
+    }
+}
+
+impl Flatten for PendulumSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.torque.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        self.torque.unflatten_from(values)
+    }
+}
+
+/// Swing a single link up from hanging down, and keep it upright. The torque
+/// is too weak to lift the link directly, so it must be pumped up.
+///
+/// The constants, dynamics and reward are those of Gym's `Pendulum-v1`: each
+/// tick costs the squared angle from upright, plus a little for speed and
+/// torque. The episode never terminates, and is truncated after
+/// [`Pendulum::MAX_STEPS`].
+pub struct Pendulum;
+
+impl Pendulum {
+    pub const GRAVITY: f32 = 10.0;
+    pub const MASS: f32 = 1.0;
+    pub const LENGTH: f32 = 1.0;
+    pub const MAX_SPEED: f32 = 8.0;
+    pub const MAX_TORQUE: f32 = 2.0;
+    /// The time per tick.
+    pub const DT: f32 = 0.05;
+    pub const MAX_STEPS: u32 = 200;
+
+    /// The reward for a tick at `theta` and `theta_vel` under `torque`.
+    #[must_use]
+    pub fn reward(theta: f32, theta_vel: f32, torque: f32) -> f32 {
+        let angle = wrap_angle(theta);
+        -(angle * angle + 0.1 * theta_vel * theta_vel + 0.001 * torque * torque)
+    }
+}
+
+const PIVOT: Vector3<f32> = Vector3::new(0.0, 0.0, 1.5);
+
+impl Model for Pendulum {
+    type World = PendulumWorld;
+    type Signals = PendulumSignals;
+
+    fn new_world() -> Self::World {
+        Self::World {
+            theta: PI + (fastrand::f32() - 0.5) / 5.0,
+            theta_vel: (fastrand::f32() - 0.5) / 5.0,
+            episode: Episode::default(),
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals { torque: 0.0 }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        let torque = signals.torque.clamp(-Self::MAX_TORQUE, Self::MAX_TORQUE);
+        let reward = Self::reward(w.theta, w.theta_vel, torque);
+
+        let (g, m, l) = (Self::GRAVITY, Self::MASS, Self::LENGTH);
+        let theta_accel = 3.0 * g / (2.0 * l) * w.theta.sin() + 3.0 / (m * l * l) * torque;
+        w.theta_vel =
+            (w.theta_vel + theta_accel * Self::DT).clamp(-Self::MAX_SPEED, Self::MAX_SPEED);
+        w.theta += w.theta_vel * Self::DT;
+        w.time += Self::DT;
+
+        w.episode.record(reward, false, Some(Self::MAX_STEPS));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const PIVOT_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+        const NODE_COLOR: Vector3<f32> = Vector3::new(0.5, 0.2, 0.3);
+        const ROD_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
+
+        const NODE_RADIUS: f32 = 0.15;
+        const ROD_RADIUS: f32 = 0.05;
+
+        let tip =
+            PIVOT + Vector3::new(world.theta.sin(), 0.0, world.theta.cos()) * Pendulum::LENGTH;
+        vec![
+            Solid::new_sphere(PIVOT, NODE_RADIUS / 2.0, PIVOT_COLOR),
+            Solid::new_cylinder(PIVOT, tip, ROD_RADIUS, ROD_COLOR),
+            Solid::new_sphere(tip, NODE_RADIUS, NODE_COLOR),
+        ]
+    }
+}
+
+// `angle` in [-pi, pi)
+pub(super) fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn constants_match_the_reference() {
+        assert_close(Pendulum::GRAVITY, 10.0);
+        assert_close(Pendulum::MASS, 1.0);
+        assert_close(Pendulum::LENGTH, 1.0);
+        assert_close(Pendulum::MAX_SPEED, 8.0);
+        assert_close(Pendulum::MAX_TORQUE, 2.0);
+        assert_close(Pendulum::DT, 0.05);
+        assert_eq!(Pendulum::MAX_STEPS, 200);
+    }
+
+    #[test]
+    fn a_step_from_horizontal_matches_the_reference() {
+        let mut world = PendulumWorld {
+            theta: PI / 2.0,
+            theta_vel: 0.0,
+            ..Pendulum::new_world()
+        };
+        Pendulum::update(&mut world, &PendulumSignals { torque: 5.0 });
+
+        // 15 rad/s^2 from gravity and 6 from the clamped torque, over 0.05 s
+        assert!((world.theta_vel - 1.05).abs() < 1e-6);
+        assert!((world.theta - (PI / 2.0 + 0.0525)).abs() < 1e-6);
+        let cost = (PI / 2.0) * (PI / 2.0) + 0.004;
+        assert!((world.episode.reward + cost).abs() < 1e-6);
+    }
+
+    #[test]
+    fn speed_is_clamped_and_angles_wrap() {
+        let mut world = PendulumWorld {
+            theta: PI / 2.0,
+            theta_vel: 7.9,
+            ..Pendulum::new_world()
+        };
+        Pendulum::update(&mut world, &PendulumSignals { torque: 2.0 });
+        assert_close(world.theta_vel, Pendulum::MAX_SPEED);
+
+        let reward = Pendulum::reward(0.5, 0.0, 0.0);
+        assert!((Pendulum::reward(0.5 + 2.0 * PI, 0.0, 0.0) - reward).abs() < 1e-5);
+        assert!((Pendulum::reward(0.5 - 4.0 * PI, 0.0, 0.0) - reward).abs() < 1e-5);
+    }
+
+    #[test]
+    fn episodes_are_truncated_without_terminating() {
+        let mut world = Pendulum::new_world();
+        let signals = Pendulum::new_signals();
+        for _ in 0..Pendulum::MAX_STEPS {
+            assert!(!world.episode.is_over());
+            Pendulum::update(&mut world, &signals);
+        }
+        assert!(world.episode.truncated && !world.episode.terminated);
+    }
+}
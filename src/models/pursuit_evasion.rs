@@ -0,0 +1,309 @@
+use super::Episode;
+use crate::{
+    linearization::Flatten,
+    multi_agent::MultiAgentModel,
+    physics::{self, Collider, Plane},
+    Model, Solid,
+};
+use cgmath::{prelude::*, Vector2, Vector3};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Pursuer,
+    Evader,
+}
+
+// Evaders turn faster, but pursuers are faster in a straight line
+impl Role {
+    #[must_use]
+    pub fn max_accel(self) -> f32 {
+        match self {
+            Role::Pursuer => 2.0,
+            Role::Evader => 3.0,
+        }
+    }
+    #[must_use]
+    pub fn max_speed(self) -> f32 {
+        match self {
+            Role::Pursuer => 1.2,
+            Role::Evader => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Agent {
+    pub role: Role,
+    pub pos: Vector2<f32>,
+    pub vel: Vector2<f32>,
+    /// Whether this is an evader that has been caught, and so stands still
+    /// for the rest of the episode.
+    pub caught: bool,
+}
+
+#[derive(Clone)]
+pub struct PursuitWorld {
+    /// The pursuers, then the evaders.
+    pub agents: Vec<Agent>,
+    /// The reward each agent earned over the last tick.
+    pub rewards: Vec<f32>,
+
+    /// The pursuers' shared reward.
+    pub episode: Episode,
+    pub time: f32,
+}
+
+pub struct PursuitSignals {
+    /// The acceleration of each agent, limited to [`Role::max_accel`] in
+    /// magnitude.
+    pub accels: Vec<Vector2<f32>>,
+}
+
+/// Another agent as seen by one, relative to its own position and velocity.
+#[derive(Clone, Copy, Debug)]
+pub struct Sighting {
+    pub offset: Vector2<f32>,
+    pub vel: Vector2<f32>,
+}
+
+/// What one agent of a [`PursuitEvasion`] perceives.
+#[derive(Clone, Debug)]
+pub struct PursuitObservation {
+    pub role: Role,
+    pub pos: Vector2<f32>,
+    pub vel: Vector2<f32>,
+    pub caught: bool,
+    /// Every other pursuer.
+    pub pursuers: Vec<Sighting>,
+    /// Every other evader that is still free.
+    pub evaders: Vec<Sighting>,
+}
+
+impl Flatten for PursuitWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        for agent in &self.agents {
+            agent.pos.flatten_into(values);
+            agent.vel.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, mut values: &'a [f64]) -> &'a [f64] {
+        for agent in &mut self.agents {
+            values = agent.pos.unflatten_from(values);
+            values = agent.vel.unflatten_from(values);
+        }
+        values
+    }
+}
+
+impl Flatten for PursuitSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        for accel in &self.accels {
+            accel.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, mut values: &'a [f64]) -> &'a [f64] {
+        for accel in &mut self.accels {
+            values = accel.unflatten_from(values);
+        }
+        values
+    }
+}
+
+/// `PURSUERS` agents chase `EVADERS` more agile ones around a square arena. A
+/// pursuer that comes within [`PursuitEvasion::CAPTURE_RADIUS`] of a free
+/// evader catches it.
+///
+/// Each agent controls its own acceleration as an agent of the
+/// [`MultiAgentModel`], pursuers first. Each catch earns every pursuer
+/// [`PursuitEvasion::CAPTURE_REWARD`] and costs the caught evader as much.
+/// Every tick, each pursuer pays [`PursuitEvasion::TIME_REWARD`] and each free
+/// evader earns it. The episode terminates once every evader is caught, and
+/// is otherwise truncated after [`PursuitEvasion::MAX_STEPS`].
+pub struct PursuitEvasion<const PURSUERS: usize = 3, const EVADERS: usize = 2>;
+
+impl<const PURSUERS: usize, const EVADERS: usize> PursuitEvasion<PURSUERS, EVADERS> {
+    /// The side length of the square arena, centered on the origin.
+    pub const ARENA_SIZE: f32 = 10.0;
+    pub const AGENT_RADIUS: f32 = 0.2;
+    /// How close the centers of a pursuer and an evader must come for a
+    /// catch.
+    pub const CAPTURE_RADIUS: f32 = 0.5;
+    pub const CAPTURE_REWARD: f32 = 10.0;
+    pub const TIME_REWARD: f32 = 0.01;
+    pub const MAX_STEPS: u32 = 3000;
+
+    /// Whether `agent` is a pursuer or an evader.
+    #[must_use]
+    pub fn role(agent: usize) -> Role {
+        if agent < PURSUERS {
+            Role::Pursuer
+        } else {
+            Role::Evader
+        }
+    }
+}
+
+impl<const PURSUERS: usize, const EVADERS: usize> Model for PursuitEvasion<PURSUERS, EVADERS> {
+    type World = PursuitWorld;
+    type Signals = PursuitSignals;
+
+    fn new_world() -> Self::World {
+        // Pursuers start on the -x half, evaders on the +x half
+        let quarter = Self::ARENA_SIZE / 4.0;
+        let agents = (0..PURSUERS + EVADERS)
+            .map(|i| {
+                let role = Self::role(i);
+                let side = if role == Role::Pursuer { -1.0 } else { 1.0 };
+                let x = side * quarter * (1.0 + fastrand::f32() * 0.9);
+                let y = (fastrand::f32() * 2.0 - 1.0) * quarter * 1.9;
+                Agent {
+                    role,
+                    pos: Vector2::new(x, y),
+                    vel: Vector2::zero(),
+                    caught: false,
+                }
+            })
+            .collect();
+        Self::World {
+            agents,
+            rewards: vec![0.0; PURSUERS + EVADERS],
+            episode: Episode::default(),
+            time: 0.0,
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            accels: vec![Vector2::zero(); PURSUERS + EVADERS],
+        }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        let dt: f32 = physics::dt();
+        let half_size = Self::ARENA_SIZE / 2.0 - Self::AGENT_RADIUS;
+        for (agent, &accel) in w.agents.iter_mut().zip(&signals.accels) {
+            if agent.caught {
+                continue;
+            }
+            let role = agent.role;
+            let mut accel = accel;
+            if accel.magnitude() > role.max_accel() {
+                accel = accel.normalize_to(role.max_accel());
+            }
+            agent.vel += accel * dt;
+            if agent.vel.magnitude() > role.max_speed() {
+                agent.vel = agent.vel.normalize_to(role.max_speed());
+            }
+            agent.pos += agent.vel * dt;
+
+            // Stop dead against the edges of the arena
+            for axis in 0..2 {
+                if agent.pos[axis].abs() > half_size {
+                    agent.pos[axis] = agent.pos[axis].clamp(-half_size, half_size);
+                    agent.vel[axis] = 0.0;
+                }
+            }
+        }
+        w.time += dt;
+
+        let (pursuers, evaders) = w.agents.split_at_mut(PURSUERS);
+        let mut pursuer_reward = -Self::TIME_REWARD;
+        for (evader, reward) in evaders.iter_mut().zip(&mut w.rewards[PURSUERS..]) {
+            if evader.caught {
+                *reward = 0.0;
+            } else if pursuers
+                .iter()
+                .any(|pursuer| pursuer.pos.distance(evader.pos) < Self::CAPTURE_RADIUS)
+            {
+                evader.caught = true;
+                evader.vel = Vector2::zero();
+                *reward = -Self::CAPTURE_REWARD;
+                pursuer_reward += Self::CAPTURE_REWARD;
+            } else {
+                *reward = Self::TIME_REWARD;
+            }
+        }
+        w.rewards[..PURSUERS].fill(pursuer_reward);
+
+        let all_caught = evaders.iter().all(|evader| evader.caught);
+        w.episode
+            .record(pursuer_reward, all_caught, Some(Self::MAX_STEPS));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const PURSUER_COLOR: Vector3<f32> = Vector3::new(0.6, 0.1, 0.1);
+        const EVADER_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
+        const CAUGHT_COLOR: Vector3<f32> = Vector3::new(0.4, 0.4, 0.4);
+        const FLOOR_COLOR: Vector3<f32> = Vector3::new(0.9, 0.9, 0.9);
+
+        const AGENT_HEIGHT: f32 = 0.3;
+
+        let mut solids: Vec<Solid> = world
+            .agents
+            .iter()
+            .map(|agent| {
+                let color = match (agent.role, agent.caught) {
+                    (Role::Pursuer, _) => PURSUER_COLOR,
+                    (Role::Evader, false) => EVADER_COLOR,
+                    (Role::Evader, true) => CAUGHT_COLOR,
+                };
+                Solid::new_cylinder(
+                    agent.pos.extend(0.0),
+                    agent.pos.extend(AGENT_HEIGHT),
+                    Self::AGENT_RADIUS,
+                    color,
+                )
+            })
+            .collect();
+        solids.extend(Plane::FLOOR.solids(FLOOR_COLOR));
+        solids
+    }
+}
+
+impl<const PURSUERS: usize, const EVADERS: usize> MultiAgentModel
+    for PursuitEvasion<PURSUERS, EVADERS>
+{
+    type Observation = PursuitObservation;
+    type AgentSignals = Vector2<f32>;
+
+    fn agent_count() -> usize {
+        PURSUERS + EVADERS
+    }
+
+    fn observe(world: &Self::World, agent: usize) -> Self::Observation {
+        let me = world.agents[agent];
+        let sightings = |role: Role| {
+            world
+                .agents
+                .iter()
+                .enumerate()
+                .filter(|&(i, other)| i != agent && other.role == role && !other.caught)
+                .map(|(_, other)| Sighting {
+                    offset: other.pos - me.pos,
+                    vel: other.vel - me.vel,
+                })
+                .collect()
+        };
+        PursuitObservation {
+            role: me.role,
+            pos: me.pos,
+            vel: me.vel,
+            caught: me.caught,
+            pursuers: sightings(Role::Pursuer),
+            evaders: sightings(Role::Evader),
+        }
+    }
+    fn reward(world: &Self::World, agent: usize) -> f32 {
+        world.rewards[agent]
+    }
+    fn agent_signals(signals: &mut Self::Signals, agent: usize) -> &mut Self::AgentSignals {
+        &mut signals.accels[agent]
+    }
+}
@@ -0,0 +1,366 @@
+use super::Episode;
+use crate::{
+    actuators::Actuator,
+    linearization::Flatten,
+    physics::{self, Collider, ContactMaterial, Gravity, PenaltyContact, Plane, RigidBody, Wrench},
+    Model, Solid,
+};
+use cgmath::{prelude::*, Quaternion, Rad, Vector1, Vector3};
+
+#[derive(Clone)]
+pub struct QuadrotorWorld {
+    pub body: RigidBody,
+    /// Turn [`QuadrotorSignals::motor_thrusts`] into applied thrusts.
+    pub motors: [Actuator<Vector1<f32>>; 4],
+    /// For drawing the rotors.
+    pub rotor_angles: [f32; 4],
+
+    /// The points the task wants the quadrotor to reach, in order.
+    pub waypoints: Vec<Vector3<f32>>,
+    /// The index of the waypoint currently sought.
+    pub waypoint: usize,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+impl QuadrotorWorld {
+    /// The waypoint currently sought, if any are left.
+    pub fn target(&self) -> Option<Vector3<f32>> {
+        self.waypoints.get(self.waypoint).copied()
+    }
+    /// The thrust each motor currently applies.
+    pub fn motor_thrusts(&self) -> [f32; 4] {
+        self.motors.map(|motor| motor.output().x)
+    }
+    /// Whether the quadrotor has turned upside down.
+    pub fn is_flipped(&self) -> bool {
+        (self.body.orientation * Vector3::unit_z()).z < 0.0
+    }
+}
+
+pub struct QuadrotorSignals {
+    /// The thrust commanded from each motor, between 0 and
+    /// [`Quadrotor::MAX_THRUST`]. Motors sit at the end of the arms along +x,
+    /// +y, -x and -y in that order.
+    pub motor_thrusts: [f32; 4],
+}
+
+/// The body and motor states.
+impl Flatten for QuadrotorWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.body.flatten_into(values);
+        for motor in &self.motors {
+            motor.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let mut values = self.body.unflatten_from(values);
+        for motor in &mut self.motors {
+            values = motor.unflatten_from(values);
+        }
+        values
+    }
+}
+
+impl Flatten for QuadrotorSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        for thrust in &self.motor_thrusts {
+            thrust.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, mut values: &'a [f64]) -> &'a [f64] {
+        for thrust in &mut self.motor_thrusts {
+            values = thrust.unflatten_from(values);
+        }
+        values
+    }
+}
+
+/// A quadrotor in plus configuration, taking off from the ground to hover at
+/// [`Quadrotor::HOVER_POINT`].
+///
+/// Each motor pushes along the body's z axis, and its rotor drags the body
+/// around that axis against its spin. Motors on the x axis spin
+/// counterclockwise seen from above, and those on the y axis clockwise.
+///
+/// Each tick costs the distance to the target plus a little for spinning.
+/// Flipping over or leaving the arena terminates the episode with a penalty.
+/// Episodes are truncated after [`Quadrotor::MAX_STEPS`].
+pub struct Quadrotor;
+
+/// A [`Quadrotor`] flying a course of random waypoints. Reaching each one
+/// earns a bonus, and reaching the last terminates the episode.
+pub struct QuadrotorWaypoints;
+
+impl Quadrotor {
+    pub const MASS: f32 = 1.0;
+    pub const INERTIA: Vector3<f32> = Vector3::new(0.01, 0.01, 0.02);
+    /// The distance from the center to each motor.
+    pub const ARM_LENGTH: f32 = 0.2;
+    pub const MAX_THRUST: f32 = 5.0;
+    /// Rotor drag torque per unit of thrust.
+    pub const DRAG_COEFFICIENT: f32 = 0.02;
+    pub const MOTOR_LAG: f32 = 0.02;
+    pub const HOVER_POINT: Vector3<f32> = Vector3::new(0.0, 0.0, 1.0);
+    pub const MAX_STEPS: u32 = 1000;
+    /// How far the quadrotor may stray from the origin before the episode
+    /// terminates.
+    pub const ARENA_RADIUS: f32 = 10.0;
+    pub const CRASH_PENALTY: f32 = 100.0;
+
+    /// The thrust each motor must give to hover.
+    #[must_use]
+    pub fn hover_thrust() -> f32 {
+        Self::MASS * -GRAVITY.accel.z / 4.0
+    }
+    /// Where each motor is, in body coordinates.
+    #[must_use]
+    pub fn motor_offsets() -> [Vector3<f32>; 4] {
+        [
+            Vector3::unit_x(),
+            Vector3::unit_y(),
+            -Vector3::unit_x(),
+            -Vector3::unit_y(),
+        ]
+        .map(|direction| direction * Self::ARM_LENGTH)
+    }
+}
+
+impl QuadrotorWaypoints {
+    pub const WAYPOINT_COUNT: usize = 4;
+    /// How close the quadrotor must come to a waypoint to reach it.
+    pub const TOLERANCE: f32 = 0.3;
+    pub const WAYPOINT_BONUS: f32 = 100.0;
+    pub const MAX_STEPS: u32 = 3000;
+}
+
+const GRAVITY: Gravity = Gravity::EARTH;
+const LINEAR_DRAG: f32 = 0.1;
+// Counterclockwise seen from above is positive
+const ROTOR_SPINS: [f32; 4] = [1.0, -1.0, 1.0, -1.0];
+// How fast the rotors are drawn spinning at full thrust, in radians per second
+const MAX_ROTOR_SPEED: f32 = 30.0;
+// The landing legs reach this far below each motor
+const LEG_LENGTH: f32 = 0.05;
+const LEG_CONTACT: PenaltyContact = PenaltyContact {
+    stiffness: 5000.0,
+    material: ContactMaterial {
+        static_friction: 0.8,
+        kinetic_friction: 0.6,
+        restitution: 0.1,
+    },
+};
+
+fn new_world(waypoints: Vec<Vector3<f32>>) -> QuadrotorWorld {
+    let motor = Actuator::ideal()
+        .with_saturation(Quadrotor::MAX_THRUST)
+        .with_lag(Quadrotor::MOTOR_LAG);
+    QuadrotorWorld {
+        body: RigidBody::new(
+            Vector3::unit_z() * LEG_LENGTH,
+            Quadrotor::MASS,
+            Quadrotor::INERTIA,
+        ),
+        motors: [motor; 4],
+        rotor_angles: [0.0; 4],
+        waypoints,
+        waypoint: 0,
+        episode: Episode::default(),
+        time: 0.0,
+    }
+}
+
+// The wrench on `body` under motor `thrusts`
+fn wrench(body: &RigidBody, thrusts: [f32; 4]) -> Wrench {
+    let up = body.orientation * Vector3::unit_z();
+    let motors: Wrench = Quadrotor::motor_offsets()
+        .into_iter()
+        .zip(thrusts)
+        .zip(ROTOR_SPINS)
+        .map(|((offset, thrust), spin)| {
+            Wrench::at_offset(up * thrust, body.orientation * offset)
+                + Wrench::torque(up * (-spin * Quadrotor::DRAG_COEFFICIENT * thrust))
+        })
+        .sum();
+
+    // Each leg carries a quarter of the mass
+    let legs: Wrench = Quadrotor::motor_offsets()
+        .into_iter()
+        .map(|offset| {
+            let foot = body.to_world(offset - Vector3::unit_z() * LEG_LENGTH);
+            let (gap, normal) = Plane::FLOOR.surface_near(foot);
+            let accel = LEG_CONTACT.accel(gap, normal, body.velocity_at(foot));
+            Wrench::at_offset(accel * (Quadrotor::MASS / 4.0), foot - body.pos)
+        })
+        .sum();
+
+    let forces = Wrench::force(GRAVITY.accel * body.mass - body.vel * LINEAR_DRAG);
+    motors + legs + forces
+}
+
+// Step `w`, advancing to the next waypoint on coming within `tolerance` of
+// the current one, if given
+fn update(
+    w: &mut QuadrotorWorld,
+    signals: &QuadrotorSignals,
+    tolerance: Option<f32>,
+    max_steps: u32,
+) {
+    let dt: f32 = physics::dt();
+    let mut thrusts = [0.0; 4];
+    for ((thrust, motor), command) in thrusts
+        .iter_mut()
+        .zip(&mut w.motors)
+        .zip(signals.motor_thrusts)
+    {
+        *thrust = motor.update(Vector1::new(command.max(0.0)), dt).x;
+    }
+    for ((angle, thrust), spin) in w.rotor_angles.iter_mut().zip(thrusts).zip(ROTOR_SPINS) {
+        let speed = MAX_ROTOR_SPEED * (thrust / Quadrotor::MAX_THRUST).sqrt();
+        *angle = (*angle + spin * speed * dt) % std::f32::consts::TAU;
+    }
+
+    w.body = w.body.step_with_rk4(|body| wrench(body, thrusts));
+    w.time += dt;
+
+    let mut reward = -0.1 * w.body.angular_vel.magnitude();
+    if let Some(target) = w.target() {
+        let distance = (w.body.pos - target).magnitude();
+        if tolerance.map_or(false, |tolerance| distance < tolerance) {
+            w.waypoint += 1;
+            reward += QuadrotorWaypoints::WAYPOINT_BONUS;
+        } else {
+            reward -= distance;
+        }
+    }
+    let crashed = w.is_flipped() || w.body.pos.magnitude() > Quadrotor::ARENA_RADIUS;
+    if crashed {
+        reward -= Quadrotor::CRASH_PENALTY;
+    }
+    let finished = w.target().is_none();
+    w.episode
+        .record(reward, crashed || finished, Some(max_steps));
+}
+
+impl Model for Quadrotor {
+    type World = QuadrotorWorld;
+    type Signals = QuadrotorSignals;
+
+    fn new_world() -> Self::World {
+        new_world(vec![Self::HOVER_POINT])
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals {
+            motor_thrusts: [0.0; 4],
+        }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+        update(w, signals, None, Self::MAX_STEPS);
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        solids(world)
+    }
+}
+
+impl Model for QuadrotorWaypoints {
+    type World = QuadrotorWorld;
+    type Signals = QuadrotorSignals;
+
+    fn new_world() -> Self::World {
+        let waypoints = (0..Self::WAYPOINT_COUNT)
+            .map(|_| {
+                Vector3::new(
+                    6.0 * fastrand::f32() - 3.0,
+                    6.0 * fastrand::f32() - 3.0,
+                    0.5 + 2.5 * fastrand::f32(),
+                )
+            })
+            .collect();
+        new_world(waypoints)
+    }
+    fn new_signals() -> Self::Signals {
+        Quadrotor::new_signals()
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+        update(w, signals, Some(Self::TOLERANCE), Self::MAX_STEPS);
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        solids(world)
+    }
+}
+
+fn solids(world: &QuadrotorWorld) -> Vec<Solid> {
+    const BODY_COLOR: Vector3<f32> = Vector3::new(0.2, 0.2, 0.25);
+    const ARM_COLOR: Vector3<f32> = Vector3::new(0.5, 0.5, 0.5);
+    const ROTOR_COLOR: Vector3<f32> = Vector3::new(0.6, 0.8, 0.9);
+    const BLADE_COLORS: [Vector3<f32>; 2] =
+        [Vector3::new(0.8, 0.2, 0.1), Vector3::new(0.1, 0.2, 0.8)];
+    const TARGET_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+    const FLOOR_COLOR: Vector3<f32> = Vector3::new(0.9, 0.9, 0.9);
+
+    const BODY_SIZE: Vector3<f32> = Vector3::new(0.12, 0.12, 0.05);
+    const ARM_RADIUS: f32 = 0.01;
+    const ROTOR_RADIUS: f32 = 0.09;
+    const ROTOR_HEIGHT: f32 = 0.03;
+    const DISC_THICKNESS: f32 = 0.004;
+    const BLADE_SIZE: Vector3<f32> = Vector3::new(0.18, 0.02, 0.006);
+    const TARGET_RADIUS: f32 = 0.05;
+
+    let body = &world.body;
+    let up = body.orientation * Vector3::unit_z();
+    let mut solids = vec![Solid::new_rectangular_cuboid(
+        BODY_SIZE,
+        body.pos,
+        body.orientation,
+        BODY_COLOR,
+    )];
+    for ((offset, angle), spin) in Quadrotor::motor_offsets()
+        .into_iter()
+        .zip(world.rotor_angles)
+        .zip(ROTOR_SPINS)
+    {
+        let motor = body.to_world(offset);
+        let hub = motor + up * ROTOR_HEIGHT;
+        let blade_color = BLADE_COLORS[usize::from(spin < 0.0)];
+        solids.extend([
+            Solid::new_cylinder(body.pos, motor, ARM_RADIUS, ARM_COLOR),
+            Solid::new_cylinder(motor, hub, ARM_RADIUS, ARM_COLOR),
+            Solid::new_cylinder(
+                hub - up * (DISC_THICKNESS / 2.0),
+                hub + up * (DISC_THICKNESS / 2.0),
+                ROTOR_RADIUS,
+                ROTOR_COLOR,
+            ),
+            Solid::new_rectangular_cuboid(
+                BLADE_SIZE,
+                hub + up * DISC_THICKNESS,
+                body.orientation * Quaternion::from_angle_z(Rad(angle)),
+                blade_color,
+            ),
+        ]);
+    }
+    if let Some(target) = world.target() {
+        solids.push(Solid::new_sphere(target, TARGET_RADIUS, TARGET_COLOR));
+    }
+    solids.extend(Plane::FLOOR.solids(FLOOR_COLOR));
+    solids
+}
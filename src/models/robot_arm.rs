@@ -0,0 +1,356 @@
+use super::Episode;
+use crate::{
+    linearization::Flatten,
+    physics::{self, Collider, Plane},
+    Model, Solid,
+};
+use cgmath::{prelude::*, Vector2, Vector3};
+use std::f32::consts::PI;
+
+#[derive(Clone)]
+pub struct RobotArmWorld<const N: usize> {
+    /// The angle of each joint from straight, positive turning towards +x.
+    /// The first joint is the shoulder, whose angle is from hanging down.
+    pub angles: [f32; N],
+    pub velocities: [f32; N],
+    pub target: Vector3<f32>,
+
+    pub episode: Episode,
+    pub time: f32,
+}
+
+impl<const N: usize> RobotArmWorld<N> {
+    /// The shoulder, then the near end of each following link.
+    pub fn joint_positions(&self) -> [Vector3<f32>; N] {
+        let mut angle = 0.0;
+        let mut pos = RobotArm::<N>::SHOULDER;
+        std::array::from_fn(|i| {
+            let joint = pos;
+            angle += self.angles[i];
+            pos += in_plane(down(angle)) * RobotArm::<N>::LINK_LENGTH;
+            joint
+        })
+    }
+    /// The tip of the last link.
+    pub fn end_effector(&self) -> Vector3<f32> {
+        let angle: f32 = self.angles.iter().sum();
+        self.joint_positions()[N - 1] + in_plane(down(angle)) * RobotArm::<N>::LINK_LENGTH
+    }
+    /// The joint angles, the joint velocities and the position of the target
+    /// relative to the end effector, in that order.
+    pub fn observation(&self) -> Vec<f32> {
+        let to_target: [f32; 3] = (self.target - self.end_effector()).into();
+        self.angles
+            .into_iter()
+            .chain(self.velocities)
+            .chain(to_target)
+            .collect()
+    }
+}
+
+pub struct RobotArmSignals<const N: usize> {
+    /// The torque at each joint, limited to [`RobotArm::max_torques`] in
+    /// either direction.
+    pub torques: [f32; N],
+}
+
+impl<const N: usize> Flatten for RobotArmWorld<N> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        for value in self.angles.iter().chain(&self.velocities) {
+            value.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, mut values: &'a [f64]) -> &'a [f64] {
+        for value in self.angles.iter_mut().chain(&mut self.velocities) {
+            values = value.unflatten_from(values);
+        }
+        values
+    }
+}
+
+impl<const N: usize> Flatten for RobotArmSignals<N> {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        for torque in &self.torques {
+            torque.flatten_into(values);
+        }
+    }
+    fn unflatten_from<'a>(&mut self, mut values: &'a [f64]) -> &'a [f64] {
+        for torque in &mut self.torques {
+            values = torque.unflatten_from(values);
+        }
+        values
+    }
+}
+
+/// A planar arm of `N` uniform links hanging from a fixed shoulder, with a
+/// motor at every joint. Move its end effector to a random target in its
+/// plane.
+///
+/// The joints turn about the y axis with a little friction, and stop dead
+/// at [`RobotArm::JOINT_LIMIT`] either way from straight. Each tick costs the
+/// distance from the end effector to the target. Coming within
+/// [`RobotArm::TOLERANCE`] of it earns [`RobotArm::TARGET_BONUS`] and
+/// terminates the episode, which is otherwise truncated after
+/// [`RobotArm::MAX_STEPS`].
+pub struct RobotArm<const N: usize>;
+
+pub type TwoLinkArm = RobotArm<2>;
+pub type ThreeLinkArm = RobotArm<3>;
+
+impl<const N: usize> RobotArm<N> {
+    pub const LINK_LENGTH: f32 = 0.5;
+    pub const LINK_MASS: f32 = 1.0;
+    /// The length of the outstretched arm.
+    // No arm has anywhere near enough links to lose precision
+    #[allow(clippy::cast_precision_loss)]
+    pub const REACH: f32 = Self::LINK_LENGTH * N as f32;
+    /// How far each joint may bend either way from straight.
+    pub const JOINT_LIMIT: f32 = 0.75 * PI;
+    pub const SHOULDER: Vector3<f32> = Vector3::new(0.0, 0.0, Self::REACH + 0.3);
+    pub const TOLERANCE: f32 = 0.05;
+    pub const TARGET_BONUS: f32 = 100.0;
+    pub const MAX_STEPS: u32 = 1000;
+
+    // Refuses to build an arm without links, once `new_world` is used
+    const HAS_LINKS: () = assert!(N > 0, "a robot arm needs at least one link");
+
+    /// The strongest torque each joint can apply: half again what it needs to
+    /// hold the links beyond it out sideways.
+    #[must_use]
+    pub fn max_torques() -> [f32; N] {
+        std::array::from_fn(|i| {
+            let links = physics::scalar::<f32>(N - i);
+            1.5 * Self::LINK_MASS * links * GRAVITY * Self::LINK_LENGTH * links / 2.0
+        })
+    }
+
+    /// The mass matrix of the chain, and the joint torques from gravity and
+    /// the links' motion, from its Lagrangian.
+    fn dynamics(angles: &[f32; N], velocities: &[f32; N]) -> ([[f32; N]; N], [f32; N]) {
+        let (mass, length) = (Self::LINK_MASS, Self::LINK_LENGTH);
+        let moment = mass * length * length / 12.0;
+
+        // The direction of each link, and how fast it turns
+        let mut absolute = [0.0; N];
+        let mut spin = [0.0; N];
+        let (mut angle, mut angle_vel) = (0.0, 0.0);
+        for i in 0..N {
+            angle += angles[i];
+            angle_vel += velocities[i];
+            absolute[i] = angle;
+            spin[i] = angle_vel;
+        }
+
+        let mut inertia = [[0.0; N]; N];
+        let mut forces = [0.0; N];
+        for k in 0..N {
+            // How the center of link k moves with each joint, and the
+            // acceleration it needs besides that from the joints speeding up
+            let mut jacobian = [Vector2::zero(); N];
+            let mut accel = Vector2::new(0.0, -GRAVITY);
+            for j in 0..=k {
+                let lever = if j == k { length / 2.0 } else { length };
+                for column in &mut jacobian[..=j] {
+                    *column += across(absolute[j]) * lever;
+                }
+                accel += down(absolute[j]) * (lever * spin[j] * spin[j]);
+            }
+            for i in 0..=k {
+                for l in 0..=k {
+                    inertia[i][l] += mass * jacobian[i].dot(jacobian[l]) + moment;
+                }
+                forces[i] += mass * jacobian[i].dot(accel);
+            }
+        }
+        (inertia, forces)
+    }
+}
+
+const GRAVITY: f32 = 9.81;
+// Folding chains whip around fast, so integrate in shorter steps than a tick
+const SUBSTEPS: usize = 10;
+// Viscous friction in the joints, relative to the strength of each joint
+const JOINT_FRICTION: f32 = 0.05;
+
+// Joint angles and velocities
+type State<const N: usize> = ([f32; N], [f32; N]);
+
+// The direction of a link at `angle` from hanging down, in the xz plane
+fn down(angle: f32) -> Vector2<f32> {
+    Vector2::new(angle.sin(), -angle.cos())
+}
+// The derivative of `down`
+fn across(angle: f32) -> Vector2<f32> {
+    Vector2::new(angle.cos(), angle.sin())
+}
+fn in_plane(v: Vector2<f32>) -> Vector3<f32> {
+    Vector3::new(v.x, 0.0, v.y)
+}
+
+// Solve `matrix * x = rhs` for symmetric positive definite `matrix`
+fn solve<const N: usize>(mut matrix: [[f32; N]; N], mut rhs: [f32; N]) -> [f32; N] {
+    for i in 0..N {
+        let (upper, lower) = matrix.split_at_mut(i + 1);
+        let pivot = &upper[i];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[i] / pivot[i];
+            for (entry, &above) in row[i..].iter_mut().zip(&pivot[i..]) {
+                *entry -= factor * above;
+            }
+            rhs[i + 1 + offset] -= factor * rhs[i];
+        }
+    }
+    let mut x = [0.0; N];
+    for i in (0..N).rev() {
+        let known: f32 = (i + 1..N).map(|column| matrix[i][column] * x[column]).sum();
+        x[i] = (rhs[i] - known) / matrix[i][i];
+    }
+    x
+}
+
+impl<const N: usize> Model for RobotArm<N> {
+    type World = RobotArmWorld<N>;
+    type Signals = RobotArmSignals<N>;
+
+    fn new_world() -> Self::World {
+        let () = Self::HAS_LINKS;
+        let mut world = Self::World {
+            angles: [0.0; N],
+            velocities: [0.0; N],
+            target: Vector3::zero(),
+            episode: Episode::default(),
+            time: 0.0,
+        };
+        // Wherever random joint angles would put the end effector, so that
+        // the arm can reach it
+        let target_angles =
+            std::array::from_fn(|_| (fastrand::f32() * 2.0 - 1.0) * Self::JOINT_LIMIT);
+        world.target = Self::World {
+            angles: target_angles,
+            ..world.clone()
+        }
+        .end_effector();
+        world
+    }
+    fn new_signals() -> Self::Signals {
+        Self::Signals { torques: [0.0; N] }
+    }
+
+    fn update(w: &mut Self::World, signals: &Self::Signals) {
+        if w.episode.is_over() {
+            *w = Self::World {
+                episode: w.episode.next(),
+                time: w.time,
+                ..Self::new_world()
+            };
+        }
+
+        let dt = physics::dt::<f32>() / physics::scalar::<f32>(SUBSTEPS);
+        let max_torques = Self::max_torques();
+        let mut torques = signals.torques;
+        for (torque, max) in torques.iter_mut().zip(max_torques) {
+            *torque = torque.clamp(-max, max);
+        }
+        let derivative = |(angles, velocities): State<N>| -> State<N> {
+            let (inertia, mut forces) = Self::dynamics(&angles, &velocities);
+            for i in 0..N {
+                forces[i] += torques[i] - max_torques[i] * JOINT_FRICTION * velocities[i];
+            }
+            (velocities, solve(inertia, forces))
+        };
+        let offset = |(angles, velocities): State<N>, (slope, accels): State<N>, dt: f32| {
+            (
+                std::array::from_fn(|i| angles[i] + slope[i] * dt),
+                std::array::from_fn(|i| velocities[i] + accels[i] * dt),
+            )
+        };
+        let mean = |k: [&[f32; N]; 4]| -> [f32; N] {
+            std::array::from_fn(|i| (k[0][i] + 2.0 * (k[1][i] + k[2][i]) + k[3][i]) / 6.0)
+        };
+        for _ in 0..SUBSTEPS {
+            let state = (w.angles, w.velocities);
+            let k1 = derivative(state);
+            let k2 = derivative(offset(state, k1, dt / 2.0));
+            let k3 = derivative(offset(state, k2, dt / 2.0));
+            let k4 = derivative(offset(state, k3, dt));
+            let slope = (
+                mean([&k1.0, &k2.0, &k3.0, &k4.0]),
+                mean([&k1.1, &k2.1, &k3.1, &k4.1]),
+            );
+            (w.angles, w.velocities) = offset(state, slope, dt);
+
+            // Joints that run into their stops take just the impulse that
+            // halts them there, which the rest of the chain feels too
+            for i in 0..N {
+                if w.angles[i].abs() > Self::JOINT_LIMIT {
+                    w.angles[i] = w.angles[i].clamp(-Self::JOINT_LIMIT, Self::JOINT_LIMIT);
+                    if w.velocities[i] * w.angles[i] > 0.0 {
+                        let (inertia, _) = Self::dynamics(&w.angles, &w.velocities);
+                        let mut unit = [0.0; N];
+                        unit[i] = 1.0;
+                        let response = solve(inertia, unit);
+                        let impulse = -w.velocities[i] / response[i];
+                        for (velocity, response) in w.velocities.iter_mut().zip(response) {
+                            *velocity += response * impulse;
+                        }
+                    }
+                }
+            }
+
+            w.time += dt;
+        }
+
+        let distance = w.end_effector().distance(w.target);
+        let reached = distance < Self::TOLERANCE;
+        let reward = if reached {
+            Self::TARGET_BONUS
+        } else {
+            -distance
+        };
+        w.episode.record(reward, reached, Some(Self::MAX_STEPS));
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        const STAND_COLOR: Vector3<f32> = Vector3::new(0.3, 0.3, 0.3);
+        const JOINT_COLOR: Vector3<f32> = Vector3::new(0.9, 0.6, 0.1);
+        const LINK_COLOR: Vector3<f32> = Vector3::new(0.0, 0.3, 0.6);
+        const EFFECTOR_COLOR: Vector3<f32> = Vector3::new(0.5, 0.2, 0.3);
+        const TARGET_COLOR: Vector3<f32> = Vector3::new(0.0, 0.5, 0.3);
+        const FLOOR_COLOR: Vector3<f32> = Vector3::new(0.9, 0.9, 0.9);
+
+        const STAND_RADIUS: f32 = 0.05;
+        const STAND_OFFSET: f32 = 0.2;
+        const JOINT_RADIUS: f32 = 0.05;
+        const LINK_RADIUS: f32 = 0.03;
+        const TARGET_RADIUS: f32 = 0.05;
+
+        let shoulder = Self::SHOULDER;
+        let behind = shoulder - Vector3::unit_y() * STAND_OFFSET;
+        let mut solids = vec![
+            Solid::new_cylinder(
+                behind.truncate().extend(0.0),
+                behind,
+                STAND_RADIUS,
+                STAND_COLOR,
+            ),
+            Solid::new_cylinder(behind, shoulder, STAND_RADIUS, STAND_COLOR),
+            Solid::new_sphere(world.target, TARGET_RADIUS, TARGET_COLOR),
+        ];
+        let joints = world.joint_positions();
+        let end_effector = world.end_effector();
+        let ends = joints.iter().skip(1).chain([&end_effector]);
+        for (&joint, &end) in joints.iter().zip(ends) {
+            solids.extend([
+                Solid::new_cylinder(joint, end, LINK_RADIUS, LINK_COLOR),
+                Solid::new_sphere(joint, JOINT_RADIUS, JOINT_COLOR),
+            ]);
+        }
+        solids.push(Solid::new_sphere(
+            end_effector,
+            JOINT_RADIUS,
+            EFFECTOR_COLOR,
+        ));
+        solids.extend(Plane::FLOOR.solids(FLOOR_COLOR));
+        solids
+    }
+}
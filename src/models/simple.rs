@@ -1,4 +1,4 @@
-use crate::{Model, Solid};
+use crate::{actuators::Actuator, linearization::Flatten, Model, Solid};
 use cgmath::{prelude::*, Vector3};
 
 #[derive(Clone)]
@@ -6,6 +6,9 @@ pub struct SimpleWorld {
     pub pos: Vector3<f32>,
     pub vel: Vector3<f32>,
     pub color: Vector3<f32>,
+    /// Turns [`SimpleSignals::accel`] into the applied acceleration. Ideal,
+    /// except in a [`SimpleModelLimited`].
+    pub actuator: Actuator<Vector3<f32>>,
 }
 
 pub struct SimpleSignals {
@@ -13,8 +16,44 @@ pub struct SimpleSignals {
     pub target_color: Vector3<f32>,
 }
 
+impl Flatten for SimpleWorld {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.pos.flatten_into(values);
+        self.vel.flatten_into(values);
+        self.color.flatten_into(values);
+        self.actuator.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.pos.unflatten_from(values);
+        let values = self.vel.unflatten_from(values);
+        let values = self.color.unflatten_from(values);
+        self.actuator.unflatten_from(values)
+    }
+}
+
+impl Flatten for SimpleSignals {
+    fn flatten_into(&self, values: &mut Vec<f64>) {
+        self.accel.flatten_into(values);
+        self.target_color.flatten_into(values);
+    }
+    fn unflatten_from<'a>(&mut self, values: &'a [f64]) -> &'a [f64] {
+        let values = self.accel.unflatten_from(values);
+        self.target_color.unflatten_from(values)
+    }
+}
+
 pub struct SimpleModel;
 
+/// A [`SimpleModel`] whose acceleration is limited in magnitude and lags
+/// behind the command.
+pub struct SimpleModelLimited;
+
+impl SimpleModelLimited {
+    pub const MAX_ACCEL: f32 = 10.0;
+    /// The time constant of the actuator.
+    pub const LAG: f32 = 0.05;
+}
+
 impl Model for SimpleModel {
     type World = SimpleWorld;
     type Signals = SimpleSignals;
@@ -24,6 +63,7 @@ impl Model for SimpleModel {
             pos: Vector3::zero(),
             vel: Vector3::zero(),
             color: Vector3::unit_x(),
+            actuator: Actuator::ideal(),
         }
     }
     fn new_signals() -> Self::Signals {
@@ -36,8 +76,9 @@ impl Model for SimpleModel {
     fn update(world: &mut Self::World, signals: &Self::Signals) {
         let dt = 0.01;
 
-        world.pos += world.vel * dt + signals.accel * (dt * dt / 2.0);
-        world.vel += dt * signals.accel;
+        let accel = world.actuator.update(signals.accel, dt);
+        world.pos += world.vel * dt + accel * (dt * dt / 2.0);
+        world.vel += dt * accel;
         world.color = (1.0 - dt) * world.color + dt * signals.target_color;
     }
 
@@ -46,3 +87,28 @@ impl Model for SimpleModel {
         vec![Solid::new_sphere(world.pos, RADIUS, world.color)]
     }
 }
+
+impl Model for SimpleModelLimited {
+    type World = SimpleWorld;
+    type Signals = SimpleSignals;
+
+    fn new_world() -> Self::World {
+        Self::World {
+            actuator: Actuator::ideal()
+                .with_saturation(Self::MAX_ACCEL)
+                .with_lag(Self::LAG),
+            ..SimpleModel::new_world()
+        }
+    }
+    fn new_signals() -> Self::Signals {
+        SimpleModel::new_signals()
+    }
+
+    fn update(world: &mut Self::World, signals: &Self::Signals) {
+        SimpleModel::update(world, signals);
+    }
+
+    fn get_solids(world: &Self::World) -> Vec<Solid> {
+        SimpleModel::get_solids(world)
+    }
+}
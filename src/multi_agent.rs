@@ -0,0 +1,73 @@
+//! Models shared by several agents, each controlled separately.
+//!
+//! A [`MultiAgentModel`] is still a [`Model`], run with a single controller
+//! over all of its signals. [`per_agent`] builds that controller from one
+//! [`AgentController`] per agent, each of which sees only its own observation
+//! and reward and writes only its own part of the signals:
+//! ```no_run
+//! use agentbox::{
+//!     models::PursuitEvasion,
+//!     multi_agent::{self, AgentController, MultiAgentModel},
+//!     Status,
+//! };
+//!
+//! type Game = PursuitEvasion;
+//! let controllers = (0..Game::agent_count())
+//!     .map(|_| -> AgentController<Game> {
+//!         Box::new(|observation, _reward, accel| *accel = -observation.vel)
+//!     })
+//!     .collect();
+//! agentbox::run_with::<Game, _>(Status::VISUAL, multi_agent::per_agent::<Game>(controllers))
+//! ```
+
+use crate::{Model, Status};
+
+/// A model whose signals are split among a fixed number of agents.
+pub trait MultiAgentModel: Model {
+    /// What a single agent perceives of the world.
+    type Observation;
+    /// The part of the signals a single agent controls.
+    type AgentSignals;
+
+    fn agent_count() -> usize;
+
+    /// What `agent` perceives of `world`.
+    fn observe(world: &Self::World, agent: usize) -> Self::Observation;
+    /// The reward `agent` earned over the last tick.
+    fn reward(world: &Self::World, agent: usize) -> f32;
+    /// The part of `signals` that `agent` controls.
+    fn agent_signals(signals: &mut Self::Signals, agent: usize) -> &mut Self::AgentSignals;
+}
+
+/// The controller of a single agent, called every tick with its observation
+/// and the reward it earned over the last tick.
+pub type AgentController<M> = Box<
+    dyn FnMut(&<M as MultiAgentModel>::Observation, f32, &mut <M as MultiAgentModel>::AgentSignals)
+        + Send,
+>;
+
+/// A controller for the whole of `M` that runs `controllers[i]` for agent
+/// `i`.
+///
+/// # Panics
+///
+/// If there is not exactly one controller per agent.
+pub fn per_agent<M: MultiAgentModel>(
+    mut controllers: Vec<AgentController<M>>,
+) -> impl FnMut(&M::World, &mut M::Signals, &mut Status) + Send {
+    assert_eq!(
+        M::agent_count(),
+        controllers.len(),
+        "Every agent needs exactly one controller.",
+    );
+    move |world, signals, _status| {
+        for (agent, controller) in controllers.iter_mut().enumerate() {
+            let observation = M::observe(world, agent);
+            controller(
+                &observation,
+                M::reward(world, agent),
+                M::agent_signals(signals, agent),
+            );
+        }
+    }
+}
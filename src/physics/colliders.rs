@@ -0,0 +1,892 @@
+use super::{contact_events::impulse_of, scalar, Body, ContactEvent, Particle};
+use crate::Solid;
+use cgmath::{prelude::*, BaseFloat, Quaternion, Vector3};
+
+/// A static shape that particles bounce off, using penalty forces.
+///
+/// Implementors describe their surface through [`Collider::surface_near`], and
+/// get the contact response from [`Collider::collide_with`].
+pub trait Collider<S: BaseFloat = f32> {
+    /// Signed distance from `point` to the surface (negative inside), and the
+    /// outward surface normal closest to `point`.
+    fn surface_near(&self, point: Vector3<S>) -> (S, Vector3<S>);
+
+    fn contact(&self) -> &PenaltyContact<S>;
+
+    /// Solids that look exactly like this collider.
+    fn solids(&self, color: Vector3<f32>) -> Vec<Solid>;
+
+    /// The acceleration of `particle` caused by touching this collider.
+    fn collide_with(&self, particle: &Particle<S>) -> Vector3<S> {
+        let (distance, normal) = self.surface_near(particle.pos);
+        self.contact()
+            .accel(distance - particle.radius, normal, particle.vel)
+    }
+
+    /// The contact reported alongside [`Collider::collide_with`], if
+    /// `particle` touches this collider. The indices identify the collider and
+    /// particle within the model.
+    fn contact_event(
+        &self,
+        index: usize,
+        particle: &Particle<S>,
+        particle_index: usize,
+    ) -> Option<ContactEvent<S>> {
+        let (distance, normal) = self.surface_near(particle.pos);
+        let gap = distance - particle.radius;
+        (gap <= S::zero()).then(|| ContactEvent {
+            first: Body::Particle(particle_index),
+            second: Body::Collider(index),
+            normal,
+            penetration: -gap,
+            impulse: impulse_of(self.contact().accel(gap, normal, particle.vel)),
+        })
+    }
+
+    /// How far along the ray from `origin` in unit `direction` the surface is,
+    /// if it is within `max_distance`. A ray starting inside hits at 0.
+    ///
+    /// By default this marches along the ray by the distance to the surface,
+    /// which suits any collider whose distance is never overestimated.
+    fn ray_cast(&self, origin: Vector3<S>, direction: Vector3<S>, max_distance: S) -> Option<S> {
+        let tolerance: S = scalar(1e-5);
+        let mut travelled = S::zero();
+        for _ in 0..MAX_MARCHES {
+            let (distance, _) = self.surface_near(origin + direction * travelled);
+            if distance < tolerance {
+                return Some(travelled);
+            }
+            travelled += distance;
+            if travelled > max_distance {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+// Rays grazing a surface approach it ever more slowly, so give up eventually
+const MAX_MARCHES: usize = 100;
+
+/// How hard a contact is, and what the touching surfaces are made of.
+#[derive(Clone, Copy, Debug)]
+pub struct PenaltyContact<S = f32> {
+    pub stiffness: S,
+    pub material: ContactMaterial<S>,
+}
+
+impl PenaltyContact {
+    pub const DEFAULT: Self = Self {
+        stiffness: 5000.0,
+        material: ContactMaterial::DEFAULT,
+    };
+}
+
+impl<S: BaseFloat> PenaltyContact<S> {
+    pub fn cast<T: BaseFloat>(&self) -> PenaltyContact<T> {
+        PenaltyContact {
+            stiffness: scalar(self.stiffness),
+            material: self.material.cast(),
+        }
+    }
+
+    /// The acceleration from a contact with gap `pos` (negative when
+    /// penetrating) along the unit `normal`, for a particle moving at `vel`.
+    pub fn accel(&self, pos: S, normal: Vector3<S>, vel: Vector3<S>) -> Vector3<S> {
+        self.accel_with_mass(pos, normal, vel, S::one())
+    }
+
+    /// As [`PenaltyContact::accel`], where `vel` is a relative velocity and
+    /// `effective_mass` the reduced mass of the two bodies in contact. Two
+    /// (unit mass) particles hitting each other have an effective mass of 0.5.
+    pub fn accel_with_mass(
+        &self,
+        pos: S,
+        normal: Vector3<S>,
+        vel: Vector3<S>,
+        effective_mass: S,
+    ) -> Vector3<S> {
+        if pos > S::zero() {
+            Vector3::zero()
+        } else {
+            let normal_vel = vel.dot(normal);
+            let tangent_vel = vel - normal * normal_vel;
+            // The surfaces push, but never pull
+            let normal_force =
+                (-self.stiffness * pos - normal_vel * self.damping(effective_mass)).max(S::zero());
+            normal * normal_force + self.material.friction(normal_force, tangent_vel)
+        }
+    }
+
+    /// The normal damping that makes the contact, seen as a damped spring,
+    /// lose exactly the energy implied by the coefficient of restitution.
+    pub fn damping(&self, effective_mass: S) -> S {
+        let pi = scalar::<S>(std::f64::consts::PI);
+        let damping_ratio = if self.material.restitution <= S::zero() {
+            S::one()
+        } else {
+            let log = self.material.restitution.min(S::one()).ln();
+            -log / (pi * pi + log * log).sqrt()
+        };
+        scalar::<S>(2.0) * damping_ratio * (self.stiffness * effective_mass).sqrt()
+    }
+}
+
+// How quickly static friction brings a sliding contact to rest. Higher rates
+// creep less while sticking, but must stay well below 2.78 / DT for RK4 to
+// remain stable.
+const STICK_RATE: f64 = 100.0;
+
+/// Coulomb friction and bounciness of a pair of touching surfaces.
+///
+/// Particles carry no spin, so a ball on a surface slides rather than rolls.
+/// Models that need rolling, such as [`crate::models::BallOnPlate`], account
+/// for it themselves.
+#[derive(Clone, Copy, Debug)]
+pub struct ContactMaterial<S = f32> {
+    /// The largest friction to normal force ratio while sticking.
+    pub static_friction: S,
+    /// The friction to normal force ratio while sliding.
+    pub kinetic_friction: S,
+    /// The ratio of separating to approaching speed in a bounce. 1 is
+    /// perfectly elastic. As a contact lets go as soon as it stops pushing,
+    /// 0 still keeps about a tenth of the approaching speed.
+    pub restitution: S,
+}
+
+impl ContactMaterial {
+    pub const DEFAULT: Self = Self {
+        static_friction: 0.6,
+        kinetic_friction: 0.5,
+        restitution: 0.9,
+    };
+    pub const ICE: Self = Self {
+        static_friction: 0.05,
+        kinetic_friction: 0.03,
+        restitution: 0.2,
+    };
+    pub const RUBBER: Self = Self {
+        static_friction: 1.0,
+        kinetic_friction: 0.8,
+        restitution: 0.8,
+    };
+    /// Perfectly bouncy and slippery.
+    pub const FRICTIONLESS: Self = Self {
+        static_friction: 0.0,
+        kinetic_friction: 0.0,
+        restitution: 1.0,
+    };
+}
+
+impl<S: BaseFloat> ContactMaterial<S> {
+    pub fn cast<T: BaseFloat>(&self) -> ContactMaterial<T> {
+        ContactMaterial {
+            static_friction: scalar(self.static_friction),
+            kinetic_friction: scalar(self.kinetic_friction),
+            restitution: scalar(self.restitution),
+        }
+    }
+
+    /// The friction on a surface sliding at `tangent_vel` while being pressed
+    /// by `normal_force`.
+    ///
+    /// Sticking is modelled as a stiff viscous force, which holds while it is
+    /// within the static friction limit. Past that the contact slides with
+    /// constant kinetic friction.
+    ///
+    /// Objects therefore never stick outright: as the sticking force only
+    /// grows with speed, a contact under a steady tangential load within the
+    /// static limit creeps at the load divided by the stick rate of 100 per
+    /// second. A particle resting on a 30° slope under Earth gravity creeps at
+    /// about 5 cm/s.
+    pub fn friction(&self, normal_force: S, tangent_vel: Vector3<S>) -> Vector3<S> {
+        let speed = tangent_vel.magnitude();
+        if speed == S::zero() {
+            return Vector3::zero();
+        }
+        let sticking_force = scalar::<S>(STICK_RATE) * speed;
+        let force = if sticking_force <= self.static_friction * normal_force {
+            sticking_force
+        } else {
+            self.kinetic_friction * normal_force
+        };
+        tangent_vel * (-force / speed)
+    }
+}
+
+// The side length of the square slab drawn for a `Plane`
+const PLANE_EXTENT: f32 = 10.0;
+const PLANE_THICKNESS: f32 = 0.1;
+// How thick a cylinder with coincident ends is drawn
+const DISK_THICKNESS: f32 = 0.01;
+
+/// The half-space `{ x : x.dot(normal) <= offset }` is solid.
+#[derive(Clone, Copy, Debug)]
+pub struct Plane<S = f32> {
+    normal: Vector3<S>,
+    offset: S,
+    contact: PenaltyContact<S>,
+}
+
+impl Plane {
+    pub const FLOOR: &'static Self = &Self {
+        normal: Vector3::new(0.0, 0.0, 1.0),
+        offset: 0.0,
+        contact: PenaltyContact::DEFAULT,
+    };
+}
+
+impl<S: BaseFloat> Plane<S> {
+    /// The plane with (not necessarily unit) `normal` at signed distance
+    /// `offset` from the origin.
+    pub fn new(normal: Vector3<S>, offset: S) -> Self {
+        Self {
+            normal: normal.normalize(),
+            offset,
+            contact: PenaltyContact::DEFAULT.cast(),
+        }
+    }
+    /// The plane through `point` with (not necessarily unit) `normal`.
+    pub fn through(point: Vector3<S>, normal: Vector3<S>) -> Self {
+        let normal = normal.normalize();
+        Self::new(normal, point.dot(normal))
+    }
+    #[must_use]
+    pub fn with_contact(self, contact: PenaltyContact<S>) -> Self {
+        Self { contact, ..self }
+    }
+    pub fn normal(&self) -> Vector3<S> {
+        self.normal
+    }
+    pub fn offset(&self) -> S {
+        self.offset
+    }
+    pub fn cast<T: BaseFloat>(&self) -> Plane<T> {
+        Plane {
+            normal: self.normal.map(scalar),
+            offset: scalar(self.offset),
+            contact: self.contact.cast(),
+        }
+    }
+}
+
+impl<S: BaseFloat> Collider<S> for Plane<S> {
+    fn surface_near(&self, point: Vector3<S>) -> (S, Vector3<S>) {
+        (point.dot(self.normal) - self.offset, self.normal)
+    }
+    fn contact(&self) -> &PenaltyContact<S> {
+        &self.contact
+    }
+    fn ray_cast(&self, origin: Vector3<S>, direction: Vector3<S>, max_distance: S) -> Option<S> {
+        let (distance, _) = self.surface_near(origin);
+        if distance <= S::zero() {
+            return Some(S::zero());
+        }
+        let approach = -direction.dot(self.normal);
+        (approach > S::zero() && distance <= approach * max_distance).then(|| distance / approach)
+    }
+    /// A thin square slab just below the surface, centered on the point
+    /// closest to the origin.
+    fn solids(&self, color: Vector3<f32>) -> Vec<Solid> {
+        let normal = to_f32(self.normal);
+        let offset = self.offset.to_f32().unwrap();
+        vec![Solid::new_rectangular_cuboid(
+            Vector3::new(PLANE_EXTENT, PLANE_EXTENT, PLANE_THICKNESS),
+            normal * (offset - PLANE_THICKNESS / 2.0),
+            Quaternion::from_arc(Vector3::unit_z(), normal, None),
+            color,
+        )]
+    }
+}
+
+/// A solid ball.
+#[derive(Clone, Copy, Debug)]
+pub struct Sphere<S = f32> {
+    center: Vector3<S>,
+    radius: S,
+    contact: PenaltyContact<S>,
+}
+
+impl<S: BaseFloat> Sphere<S> {
+    pub fn new(center: Vector3<S>, radius: S) -> Self {
+        Self {
+            center,
+            radius,
+            contact: PenaltyContact::DEFAULT.cast(),
+        }
+    }
+    #[must_use]
+    pub fn with_contact(self, contact: PenaltyContact<S>) -> Self {
+        Self { contact, ..self }
+    }
+}
+
+impl<S: BaseFloat> Collider<S> for Sphere<S> {
+    fn surface_near(&self, point: Vector3<S>) -> (S, Vector3<S>) {
+        let rel_pos = point - self.center;
+        let distance = rel_pos.magnitude();
+        (distance - self.radius, direction_or_up(rel_pos, distance))
+    }
+    fn contact(&self) -> &PenaltyContact<S> {
+        &self.contact
+    }
+    fn solids(&self, color: Vector3<f32>) -> Vec<Solid> {
+        vec![Solid::new_sphere(
+            to_f32(self.center),
+            self.radius.to_f32().unwrap(),
+            color,
+        )]
+    }
+}
+
+/// An oriented rectangular cuboid, such as a wall, ramp or box.
+#[derive(Clone, Copy, Debug)]
+pub struct Cuboid<S = f32> {
+    half_dimensions: Vector3<S>,
+    center: Vector3<S>,
+    orientation: Quaternion<S>,
+    contact: PenaltyContact<S>,
+}
+
+impl<S: BaseFloat> Cuboid<S> {
+    /// A cuboid with width-depth-height given by `dimensions`, as in
+    /// [`Solid::new_rectangular_cuboid`].
+    pub fn new(dimensions: Vector3<S>, center: Vector3<S>, orientation: Quaternion<S>) -> Self {
+        Self {
+            half_dimensions: dimensions / scalar(2.0),
+            center,
+            orientation: orientation.normalize(),
+            contact: PenaltyContact::DEFAULT.cast(),
+        }
+    }
+    #[must_use]
+    pub fn with_contact(self, contact: PenaltyContact<S>) -> Self {
+        Self { contact, ..self }
+    }
+}
+
+impl<S: BaseFloat> Collider<S> for Cuboid<S> {
+    fn surface_near(&self, point: Vector3<S>) -> (S, Vector3<S>) {
+        let zero = S::zero();
+        let local = self.orientation.conjugate() * (point - self.center);
+        let h = self.half_dimensions;
+        let q = Vector3::new(
+            local.x.abs() - h.x,
+            local.y.abs() - h.y,
+            local.z.abs() - h.z,
+        );
+        let sign = local.map(|c| if c < zero { -S::one() } else { S::one() });
+
+        let (distance, local_normal) = if q.x > zero || q.y > zero || q.z > zero {
+            // Outside: the closest point is on a face, edge or corner
+            let outside = q.map(|c| c.max(zero));
+            let distance = outside.magnitude();
+            (distance, outside.mul_element_wise(sign) / distance)
+        } else if q.x >= q.y && q.x >= q.z {
+            (q.x, Vector3::unit_x() * sign.x)
+        } else if q.y >= q.z {
+            (q.y, Vector3::unit_y() * sign.y)
+        } else {
+            (q.z, Vector3::unit_z() * sign.z)
+        };
+        (distance, self.orientation * local_normal)
+    }
+    fn contact(&self) -> &PenaltyContact<S> {
+        &self.contact
+    }
+    /// Exact, by clipping the ray to the slab between each pair of faces.
+    fn ray_cast(&self, origin: Vector3<S>, direction: Vector3<S>, max_distance: S) -> Option<S> {
+        let to_local = self.orientation.conjugate();
+        let origin = to_local * (origin - self.center);
+        let direction = to_local * direction;
+        let (mut near, mut far) = (S::zero(), max_distance);
+        for axis in 0..3 {
+            let (o, d, h) = (origin[axis], direction[axis], self.half_dimensions[axis]);
+            if d == S::zero() {
+                if o.abs() > h {
+                    return None;
+                }
+                continue;
+            }
+            let (t1, t2) = ((-h - o) / d, (h - o) / d);
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+        (near <= far).then_some(near)
+    }
+    fn solids(&self, color: Vector3<f32>) -> Vec<Solid> {
+        vec![Solid::new_rectangular_cuboid(
+            to_f32(self.half_dimensions) * 2.0,
+            to_f32(self.center),
+            self.orientation.cast().unwrap(),
+            color,
+        )]
+    }
+}
+
+/// A solid cylinder with flat ends.
+#[derive(Clone, Copy, Debug)]
+pub struct Cylinder<S = f32> {
+    first: Vector3<S>,
+    second: Vector3<S>,
+    radius: S,
+    contact: PenaltyContact<S>,
+}
+
+impl<S: BaseFloat> Cylinder<S> {
+    /// A cylinder where `first` and `second` are the centers of the end disks,
+    /// as in [`Solid::new_cylinder`]. If they coincide, the cylinder is a disk
+    /// in the xy plane.
+    pub fn new(first: Vector3<S>, second: Vector3<S>, radius: S) -> Self {
+        Self {
+            first,
+            second,
+            radius,
+            contact: PenaltyContact::DEFAULT.cast(),
+        }
+    }
+    #[must_use]
+    pub fn with_contact(self, contact: PenaltyContact<S>) -> Self {
+        Self { contact, ..self }
+    }
+}
+
+impl<S: BaseFloat> Collider<S> for Cylinder<S> {
+    fn surface_near(&self, point: Vector3<S>) -> (S, Vector3<S>) {
+        let midpoint = (self.first + self.second) / scalar(2.0);
+        let half_length = (self.second - midpoint).magnitude();
+        let axis = direction_or_up(self.second - midpoint, half_length);
+
+        let rel_pos = point - midpoint;
+        let along = rel_pos.dot(axis);
+        let radial = rel_pos - axis * along;
+        let radial_distance = radial.magnitude();
+        let radial_normal = direction_or_up(radial, radial_distance);
+        let axial_normal = axis * along.signum();
+
+        // Distances outside the side and outside the ends respectively
+        let side = radial_distance - self.radius;
+        let end = along.abs() - half_length;
+        if side > S::zero() && end > S::zero() {
+            // Closest to the rim
+            let distance = (side * side + end * end).sqrt();
+            let normal = (radial_normal * side + axial_normal * end) / distance;
+            (distance, normal)
+        } else if side > end {
+            (side, radial_normal)
+        } else {
+            (end, axial_normal)
+        }
+    }
+    fn contact(&self) -> &PenaltyContact<S> {
+        &self.contact
+    }
+    /// A cylinder, or a thin disk in the xy plane if the ends coincide.
+    fn solids(&self, color: Vector3<f32>) -> Vec<Solid> {
+        let (mut first, mut second) = (to_f32(self.first), to_f32(self.second));
+        if first == second {
+            let half_thickness = Vector3::unit_z() * (DISK_THICKNESS / 2.0);
+            (first, second) = (first - half_thickness, second + half_thickness);
+        }
+        vec![Solid::new_cylinder(
+            first,
+            second,
+            self.radius.to_f32().unwrap(),
+            color,
+        )]
+    }
+}
+
+/// A cylinder with hemispherical ends, i.e. all points within `radius` of the
+/// segment between `first` and `second`.
+#[derive(Clone, Copy, Debug)]
+pub struct Capsule<S = f32> {
+    first: Vector3<S>,
+    second: Vector3<S>,
+    radius: S,
+    contact: PenaltyContact<S>,
+}
+
+impl<S: BaseFloat> Capsule<S> {
+    pub fn new(first: Vector3<S>, second: Vector3<S>, radius: S) -> Self {
+        Self {
+            first,
+            second,
+            radius,
+            contact: PenaltyContact::DEFAULT.cast(),
+        }
+    }
+    #[must_use]
+    pub fn with_contact(self, contact: PenaltyContact<S>) -> Self {
+        Self { contact, ..self }
+    }
+}
+
+impl<S: BaseFloat> Collider<S> for Capsule<S> {
+    fn surface_near(&self, point: Vector3<S>) -> (S, Vector3<S>) {
+        let segment = self.second - self.first;
+        let t = ((point - self.first).dot(segment) / segment.magnitude2())
+            .max(S::zero())
+            .min(S::one());
+        let rel_pos = point - (self.first + segment * t);
+        let distance = rel_pos.magnitude();
+        (distance - self.radius, direction_or_up(rel_pos, distance))
+    }
+    fn contact(&self) -> &PenaltyContact<S> {
+        &self.contact
+    }
+    fn solids(&self, color: Vector3<f32>) -> Vec<Solid> {
+        let (first, second) = (to_f32(self.first), to_f32(self.second));
+        let radius = self.radius.to_f32().unwrap();
+        vec![
+            Solid::new_cylinder(first, second, radius, color),
+            Solid::new_sphere(first, radius, color),
+            Solid::new_sphere(second, radius, color),
+        ]
+    }
+}
+
+// Normalize `v` with known magnitude, picking an arbitrary direction for zero
+fn direction_or_up<S: BaseFloat>(v: Vector3<S>, magnitude: S) -> Vector3<S> {
+    if magnitude > S::zero() {
+        v / magnitude
+    } else {
+        Vector3::unit_z()
+    }
+}
+
+fn to_f32<S: BaseFloat>(v: Vector3<S>) -> Vector3<f32> {
+    v.map(scalar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_surface(
+        collider: &impl Collider<f64>,
+        point: [f64; 3],
+        distance: f64,
+        normal: [f64; 3],
+    ) {
+        let (actual_distance, actual_normal) = collider.surface_near(point.into());
+        assert!(
+            (actual_distance - distance).abs() < 1e-9,
+            "distance {actual_distance} at {point:?}, expected {distance}",
+        );
+        assert!(
+            (actual_normal - Vector3::from(normal)).magnitude() < 1e-9,
+            "normal {actual_normal:?} at {point:?}, expected {normal:?}",
+        );
+    }
+
+    // Outside the surface, the normal is the gradient of the distance
+    fn assert_normals_are_gradients(collider: &impl Collider<f64>) {
+        const STEP: f64 = 1e-6;
+        let rng = fastrand::Rng::with_seed(3);
+        for _ in 0..1000 {
+            let point = Vector3::new(rng.f64(), rng.f64(), rng.f64()).map(|c| 6.0 * c - 3.0);
+            let (distance, normal) = collider.surface_near(point);
+            assert!((normal.magnitude() - 1.0).abs() < 1e-9);
+            if distance > 0.0 {
+                let gradient = Vector3::new(
+                    collider.surface_near(point + Vector3::unit_x() * STEP).0 - distance,
+                    collider.surface_near(point + Vector3::unit_y() * STEP).0 - distance,
+                    collider.surface_near(point + Vector3::unit_z() * STEP).0 - distance,
+                ) / STEP;
+                assert!(
+                    (gradient - normal).magnitude() < 1e-3,
+                    "normal {normal:?} at {point:?}, but gradient {gradient:?}",
+                );
+            }
+        }
+    }
+
+    fn assert_ray(
+        collider: &impl Collider<f64>,
+        origin: [f64; 3],
+        direction: [f64; 3],
+        expected: Option<f64>,
+    ) {
+        let direction = Vector3::from(direction).normalize();
+        let hit = collider.ray_cast(origin.into(), direction, 10.0);
+        let close = match (hit, expected) {
+            (Some(hit), Some(expected)) => (hit - expected).abs() < 1e-4,
+            (hit, expected) => hit == expected,
+        };
+        assert!(
+            close,
+            "ray from {origin:?} along {direction:?} hit at {hit:?}, expected {expected:?}",
+        );
+    }
+
+    #[test]
+    fn plane_surface() {
+        let plane = Plane::through(Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 2.0));
+        assert_surface(&plane, [5.0, -3.0, 3.0], 2.0, [0.0, 0.0, 1.0]);
+        assert_surface(&plane, [0.0, 0.0, 0.5], -0.5, [0.0, 0.0, 1.0]);
+        assert_normals_are_gradients(&plane);
+    }
+
+    #[test]
+    fn plane_ray_cast() {
+        let plane = Plane::through(Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 2.0));
+        assert_ray(&plane, [0.0, 0.0, 3.0], [0.0, 0.0, -1.0], Some(2.0));
+        assert_ray(
+            &plane,
+            [0.0, 0.0, 3.0],
+            [1.0, 0.0, -1.0],
+            Some(8.0_f64.sqrt()),
+        );
+        assert_ray(&plane, [0.0, 0.0, 3.0], [0.0, 0.0, 1.0], None);
+        assert_ray(&plane, [0.0, 0.0, 3.0], [1.0, 0.0, 0.0], None);
+        assert_ray(&plane, [0.0, 0.0, 30.0], [0.0, 0.0, -1.0], None);
+        assert_ray(&plane, [0.0, 0.0, 0.5], [0.0, 0.0, 1.0], Some(0.0));
+    }
+
+    #[test]
+    fn sphere_surface() {
+        let sphere = Sphere::new(Vector3::new(1.0, 0.0, 0.0), 0.5);
+        assert_surface(&sphere, [1.0, 2.0, 0.0], 1.5, [0.0, 1.0, 0.0]);
+        assert_surface(&sphere, [0.8, 0.0, 0.0], -0.3, [-1.0, 0.0, 0.0]);
+        assert_surface(&sphere, [1.0, 0.0, 0.0], -0.5, [0.0, 0.0, 1.0]);
+        assert_normals_are_gradients(&sphere);
+    }
+
+    #[test]
+    fn sphere_ray_cast() {
+        let sphere = Sphere::new(Vector3::new(1.0, 0.0, 0.0), 0.5);
+        assert_ray(&sphere, [-2.0, 0.0, 0.0], [1.0, 0.0, 0.0], Some(2.5));
+        assert_ray(&sphere, [-2.0, 0.3, 0.0], [1.0, 0.0, 0.0], Some(2.6));
+        assert_ray(&sphere, [-2.0, 1.0, 0.0], [1.0, 0.0, 0.0], None);
+        assert_ray(&sphere, [-2.0, 0.0, 0.0], [-1.0, 0.0, 0.0], None);
+        assert_ray(&sphere, [-20.0, 0.0, 0.0], [1.0, 0.0, 0.0], None);
+        assert_ray(&sphere, [1.2, 0.0, 0.0], [0.0, 1.0, 0.0], Some(0.0));
+    }
+
+    #[test]
+    fn cuboid_surface() {
+        let cuboid = Cuboid::new(
+            Vector3::new(2.0, 1.0, 1.0),
+            Vector3::zero(),
+            Quaternion::from_angle_z(cgmath::Deg(90.0)),
+        );
+        // Rotated, so the long side lies along y
+        assert_surface(&cuboid, [0.0, 3.0, 0.0], 2.0, [0.0, 1.0, 0.0]);
+        assert_surface(&cuboid, [0.0, 0.9, 0.0], -0.1, [0.0, 1.0, 0.0]);
+        assert_surface(&cuboid, [1.5, 2.0, 0.0], 1.0_f64.hypot(1.0), {
+            let diagonal = 0.5_f64.sqrt();
+            [diagonal, diagonal, 0.0]
+        });
+        assert_normals_are_gradients(&cuboid);
+    }
+
+    #[test]
+    fn cuboid_ray_cast() {
+        let cuboid = Cuboid::new(
+            Vector3::new(2.0, 1.0, 1.0),
+            Vector3::zero(),
+            Quaternion::from_angle_z(cgmath::Deg(90.0)),
+        );
+        assert_ray(&cuboid, [0.0, 3.0, 0.0], [0.0, -1.0, 0.0], Some(2.0));
+        assert_ray(&cuboid, [3.0, 0.0, 0.0], [-1.0, 0.0, 0.0], Some(2.5));
+        assert_ray(
+            &cuboid,
+            [3.0, 3.0, 0.0],
+            [-1.0, -1.0, 0.0],
+            Some(2.5 * 2.0_f64.sqrt()),
+        );
+        assert_ray(&cuboid, [3.0, 2.0, 0.0], [-1.0, 0.0, 0.0], None);
+        assert_ray(&cuboid, [3.0, 0.0, 0.0], [1.0, 0.0, 0.0], None);
+        assert_ray(&cuboid, [30.0, 0.0, 0.0], [-1.0, 0.0, 0.0], None);
+        assert_ray(&cuboid, [0.0, 0.5, 0.0], [1.0, 0.0, 0.0], Some(0.0));
+    }
+
+    #[test]
+    fn cylinder_surface() {
+        let cylinder = Cylinder::new(
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0.5,
+        );
+        assert_surface(&cylinder, [2.0, 0.0, 0.0], 1.5, [1.0, 0.0, 0.0]);
+        assert_surface(&cylinder, [0.0, 0.0, -3.0], 2.0, [0.0, 0.0, -1.0]);
+        assert_surface(&cylinder, [0.0, 0.4, 0.0], -0.1, [0.0, 1.0, 0.0]);
+        assert_surface(&cylinder, [0.0, 3.5, 5.0], 5.0, [0.0, 0.6, 0.8]);
+        assert_normals_are_gradients(&cylinder);
+    }
+
+    #[test]
+    fn cylinder_ray_cast() {
+        let cylinder = Cylinder::new(
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0.5,
+        );
+        assert_ray(&cylinder, [2.0, 0.0, 0.0], [-1.0, 0.0, 0.0], Some(1.5));
+        assert_ray(&cylinder, [0.0, 0.0, -3.0], [0.0, 0.0, 1.0], Some(2.0));
+        assert_ray(
+            &cylinder,
+            [2.0, 0.0, 2.0],
+            [-1.0, 0.0, -1.0],
+            Some(1.5 * 2.0_f64.sqrt()),
+        );
+        assert_ray(&cylinder, [2.0, 0.6, 0.0], [-1.0, 0.0, 0.0], None);
+        assert_ray(&cylinder, [0.0, 0.0, 1.5], [1.0, 0.0, 0.0], None);
+        assert_ray(&cylinder, [0.0, 0.0, 0.0], [0.0, 0.0, 1.0], Some(0.0));
+    }
+
+    #[test]
+    fn degenerate_cylinder_is_a_disk() {
+        let center = Vector3::new(1.0, 0.0, 0.0);
+        let disk = Cylinder::new(center, center, 0.5);
+        assert_surface(&disk, [1.0, 0.0, 2.0], 2.0, [0.0, 0.0, 1.0]);
+        assert_surface(&disk, [3.0, 0.0, 0.0], 1.5, [1.0, 0.0, 0.0]);
+        assert_normals_are_gradients(&disk);
+    }
+
+    #[test]
+    fn degenerate_cylinder_ray_cast() {
+        let center = Vector3::new(1.0, 0.0, 0.0);
+        let disk = Cylinder::new(center, center, 0.5);
+        assert_ray(&disk, [1.0, 0.0, 2.0], [0.0, 0.0, -1.0], Some(2.0));
+        assert_ray(&disk, [1.0, 0.0, -2.0], [0.0, 0.0, 1.0], Some(2.0));
+        assert_ray(&disk, [1.6, 0.0, 2.0], [0.0, 0.0, -1.0], None);
+    }
+
+    #[test]
+    fn degenerate_cylinder_draws_a_thin_disk() {
+        let center = Vector3::new(1.0, 0.0, 0.0);
+        let solids = Cylinder::new(center, center, 0.5).solids(Vector3::zero());
+        assert_eq!(solids.len(), 1);
+        solids[0].assert_valid();
+        let (min, max) = solids[0].bounding_aabb();
+        let size = max - min;
+        assert!((size.x - 1.0).abs() < 1e-5 && (size.y - 1.0).abs() < 1e-5);
+        assert!((size.z - DISK_THICKNESS).abs() < 1e-5);
+    }
+
+    #[test]
+    fn capsule_surface() {
+        let capsule = Capsule::new(
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            0.5,
+        );
+        assert_surface(&capsule, [0.0, 0.0, 2.0], 1.5, [0.0, 0.0, 1.0]);
+        assert_surface(&capsule, [3.0, 0.0, 0.0], 1.5, [1.0, 0.0, 0.0]);
+        assert_surface(&capsule, [0.5, -0.25, 0.0], -0.25, [0.0, -1.0, 0.0]);
+        assert_normals_are_gradients(&capsule);
+    }
+
+    #[test]
+    fn capsule_ray_cast() {
+        let capsule = Capsule::new(
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            0.5,
+        );
+        assert_ray(&capsule, [0.0, 0.0, 2.0], [0.0, 0.0, -1.0], Some(1.5));
+        assert_ray(&capsule, [3.0, 0.0, 0.0], [-1.0, 0.0, 0.0], Some(1.5));
+        assert_ray(&capsule, [3.0, 0.3, 0.0], [-1.0, 0.0, 0.0], Some(1.6));
+        assert_ray(&capsule, [0.0, 0.6, 2.0], [0.0, 0.0, -1.0], None);
+        assert_ray(&capsule, [0.0, 0.0, 0.0], [0.0, 1.0, 0.0], Some(0.0));
+    }
+
+    const GRAVITY: Vector3<f64> = Vector3::new(0.0, 0.0, -9.81);
+
+    // Step `particle` against `plane`, with or without gravity, returning
+    // where it ends up after each step
+    fn run(
+        plane: &Plane<f64>,
+        particle: Particle<f64>,
+        gravity: bool,
+        steps: usize,
+    ) -> Vec<Particle<f64>> {
+        let gravity = if gravity { GRAVITY } else { Vector3::zero() };
+        let mut particles = vec![particle];
+        (0..steps)
+            .map(|_| {
+                particles = crate::physics::time_step_with_rk4(&particles, &(), |particles, _| {
+                    particles
+                        .iter()
+                        .map(|particle| plane.collide_with(particle) + gravity)
+                        .collect()
+                });
+                particles[0]
+            })
+            .collect()
+    }
+
+    fn material(
+        static_friction: f64,
+        kinetic_friction: f64,
+        restitution: f64,
+    ) -> PenaltyContact<f64> {
+        PenaltyContact {
+            material: ContactMaterial {
+                static_friction,
+                kinetic_friction,
+                restitution,
+            },
+            ..PenaltyContact::DEFAULT.cast()
+        }
+    }
+
+    #[test]
+    fn resting_contacts_balance_gravity() {
+        let floor = Plane::through(Vector3::zero(), Vector3::unit_z());
+        let start = Particle::new(Vector3::new(0.0, 0.0, 0.1), Vector3::zero(), 0.1);
+        let rest = *run(&floor, start, true, 300).last().unwrap();
+        let depth = 0.1 - rest.pos.z;
+        let expected = GRAVITY.magnitude() / PenaltyContact::DEFAULT.stiffness as f64;
+        assert!(
+            (depth - expected).abs() < 1e-5,
+            "sank {depth}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn bounces_keep_their_restitution() {
+        let floor = Plane::through(Vector3::zero(), Vector3::unit_z());
+        let start = Particle::new(Vector3::new(0.0, 0.0, 0.2), -Vector3::unit_z(), 0.1);
+        for (restitution, low, high) in [(0.0, 0.0, 0.15), (0.5, 0.45, 0.55), (0.9, 0.85, 0.92)] {
+            let floor = floor.with_contact(material(0.0, 0.0, restitution));
+            let bounce = run(&floor, start, false, 100).last().unwrap().vel.z;
+            assert!(
+                (low..high).contains(&bounce),
+                "restitution {restitution} bounced at {bounce}",
+            );
+        }
+    }
+
+    #[test]
+    fn static_friction_creeps_at_the_documented_rate() {
+        // A 30 degree slope, steep enough to slide on with less friction
+        let normal = Vector3::new(-0.5, 0.0, 3.0_f64.sqrt() / 2.0);
+        let slope = Plane::through(Vector3::zero(), normal);
+        let depth = GRAVITY.magnitude() * normal.z / PenaltyContact::DEFAULT.stiffness as f64;
+        let start = Particle::new(normal * (0.1 - depth), Vector3::zero(), 0.1);
+
+        let sticking = slope.with_contact(material(0.9, 0.8, 0.5));
+        let load = GRAVITY.magnitude() / 2.0;
+        let creep = run(&sticking, start, true, 200)
+            .last()
+            .unwrap()
+            .vel
+            .magnitude();
+        assert!(creep <= load / STICK_RATE * 1.001, "crept at {creep}");
+
+        let sliding = slope.with_contact(material(0.3, 0.2, 0.5));
+        let accel = GRAVITY.magnitude() * (0.5 - 0.2 * normal.z);
+        let speed = run(&sliding, start, true, 100)
+            .last()
+            .unwrap()
+            .vel
+            .magnitude();
+        assert!(
+            (speed - accel).abs() < 0.01,
+            "slid at {speed}, expected {accel}"
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use super::{scalar, Particle, PenaltyContact};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+
+/// A touching pair of particles, as found by [`CollisionWorld`].
+#[derive(Clone, Copy, Debug)]
+pub struct Contact<S = f32> {
+    /// Index of the first particle. Always less than `second`.
+    pub first: usize,
+    pub second: usize,
+    /// Unit vector pointing from `second` towards `first`.
+    pub normal: Vector3<S>,
+    /// How far the two spheres overlap. Always positive.
+    pub penetration: S,
+}
+
+/// A broadphase for particle-particle collisions, bucketing particles into a
+/// uniform grid of cubic cells that is stored as a spatial hash.
+///
+/// Cells are at least as large as the largest particle diameter, so any
+/// touching pair lies in adjacent cells. Building is `O(n)` with a counting
+/// sort into flat arrays, so it is cheap enough to redo in every RK4 stage.
+pub struct CollisionWorld {
+    inverse_cell_size: f64,
+    // Cell coordinate of every particle
+    cells: Vec<[i32; 3]>,
+    // Particle indices sorted by bucket, and where each bucket starts
+    entries: Vec<u32>,
+    bucket_starts: Vec<u32>,
+}
+
+impl CollisionWorld {
+    /// Build a broadphase over `particles`.
+    pub fn new<S: BaseFloat>(particles: &[Particle<S>]) -> Self {
+        let mut world = Self {
+            inverse_cell_size: 1.0,
+            cells: Vec::new(),
+            entries: Vec::new(),
+            bucket_starts: Vec::new(),
+        };
+        world.rebuild(particles);
+        world
+    }
+
+    /// Rebuild for a new set of particles, reusing previous allocations.
+    pub fn rebuild<S: BaseFloat>(&mut self, particles: &[Particle<S>]) {
+        let max_radius = particles
+            .iter()
+            .map(|p| scalar::<f64>(p.radius))
+            .fold(0.0, f64::max);
+        self.inverse_cell_size = if max_radius > 0.0 {
+            1.0 / (2.0 * max_radius)
+        } else {
+            1.0
+        };
+
+        let bucket_count = (2 * particles.len()).next_power_of_two();
+        let inverse_cell_size = self.inverse_cell_size;
+        self.cells.clear();
+        self.cells
+            .extend(particles.iter().map(|p| cell_of(p.pos, inverse_cell_size)));
+
+        // Counting sort of particle indices by bucket
+        self.bucket_starts.clear();
+        self.bucket_starts.resize(bucket_count + 1, 0);
+        for &cell in &self.cells {
+            self.bucket_starts[bucket_of(cell, bucket_count) + 1] += 1;
+        }
+        for i in 0..bucket_count {
+            self.bucket_starts[i + 1] += self.bucket_starts[i];
+        }
+        self.entries.clear();
+        self.entries.resize(particles.len(), 0);
+        for (i, &cell) in (0..).zip(&self.cells) {
+            let bucket = bucket_of(cell, bucket_count);
+            self.entries[self.bucket_starts[bucket] as usize] = i;
+            self.bucket_starts[bucket] += 1;
+        }
+        // Every start has been advanced to the next one, so shift them back
+        self.bucket_starts.copy_within(..bucket_count, 1);
+        self.bucket_starts[0] = 0;
+    }
+
+    /// Call `f` once for every touching pair. `particles` must be the slice
+    /// this broadphase was last built from.
+    ///
+    /// # Panics
+    ///
+    /// If `particles` is not as long as the slice this was built from.
+    pub fn for_each_contact<S: BaseFloat>(
+        &self,
+        particles: &[Particle<S>],
+        mut f: impl FnMut(Contact<S>),
+    ) {
+        assert_eq!(particles.len(), self.cells.len());
+        let bucket_count = self.bucket_starts.len() - 1;
+
+        for (first, &[x, y, z]) in self.cells.iter().enumerate() {
+            for neighbour in NEIGHBOURHOOD.iter().map(|[dx, dy, dz]| {
+                [
+                    x.wrapping_add(*dx),
+                    y.wrapping_add(*dy),
+                    z.wrapping_add(*dz),
+                ]
+            }) {
+                let bucket = bucket_of(neighbour, bucket_count);
+                let range =
+                    self.bucket_starts[bucket] as usize..self.bucket_starts[bucket + 1] as usize;
+                for &second in &self.entries[range] {
+                    let second = second as usize;
+                    // Skip pairs seen from the other side, and hash collisions
+                    if second <= first || self.cells[second] != neighbour {
+                        continue;
+                    }
+                    if let Some(contact) = contact_between(particles, first, second) {
+                        f(contact);
+                    }
+                }
+            }
+        }
+    }
+
+    /// All touching pairs. `particles` must be the slice this broadphase was
+    /// last built from.
+    pub fn contacts<S: BaseFloat>(&self, particles: &[Particle<S>]) -> Vec<Contact<S>> {
+        let mut contacts = Vec::new();
+        self.for_each_contact(particles, |contact| contacts.push(contact));
+        contacts
+    }
+
+    /// The summed [`Particle::accel_from_collision_with`] for every particle,
+    /// as would be computed by checking all pairs.
+    pub fn accels_from_collisions<S: BaseFloat>(
+        &self,
+        particles: &[Particle<S>],
+    ) -> Vec<Vector3<S>> {
+        let mut accels = vec![Vector3::zero(); particles.len()];
+        self.for_each_contact(particles, |Contact { first, second, .. }| {
+            accels[first] += particles[first].accel_from_collision_with(&particles[second]);
+            accels[second] += particles[second].accel_from_collision_with(&particles[first]);
+        });
+        accels
+    }
+
+    /// The summed [`Particle::accel_from_contact_with`] for every particle.
+    pub fn accels_from_contacts<S: BaseFloat>(
+        &self,
+        particles: &[Particle<S>],
+        contact: &PenaltyContact<S>,
+    ) -> Vec<Vector3<S>> {
+        let mut accels = vec![Vector3::zero(); particles.len()];
+        self.for_each_contact(particles, |Contact { first, second, .. }| {
+            let accel = particles[first].accel_from_contact_with(&particles[second], contact);
+            accels[first] += accel;
+            accels[second] -= accel;
+        });
+        accels
+    }
+}
+
+const NEIGHBOURHOOD: [[i32; 3]; 27] = {
+    let mut offsets = [[0; 3]; 27];
+    let mut i = 0;
+    let mut dz = -1;
+    while dz <= 1 {
+        let mut dy = -1;
+        while dy <= 1 {
+            let mut dx = -1;
+            while dx <= 1 {
+                offsets[i] = [dx, dy, dz];
+                i += 1;
+                dx += 1;
+            }
+            dy += 1;
+        }
+        dz += 1;
+    }
+    offsets
+};
+
+// Coordinates out of range saturate, so far away particles merely share cells
+#[allow(clippy::cast_possible_truncation)]
+fn cell_of<S: BaseFloat>(pos: Vector3<S>, inverse_cell_size: f64) -> [i32; 3] {
+    let scaled = pos.map(scalar::<f64>) * inverse_cell_size;
+    [
+        scaled.x.floor() as i32,
+        scaled.y.floor() as i32,
+        scaled.z.floor() as i32,
+    ]
+}
+
+// `bucket_count` is a power of two
+fn bucket_of([x, y, z]: [i32; 3], bucket_count: usize) -> usize {
+    let bits = |coordinate: i32| u32::from_ne_bytes(coordinate.to_ne_bytes());
+    let hash = bits(x).wrapping_mul(73_856_093)
+        ^ bits(y).wrapping_mul(19_349_663)
+        ^ bits(z).wrapping_mul(83_492_791);
+    hash as usize & (bucket_count - 1)
+}
+
+fn contact_between<S: BaseFloat>(
+    particles: &[Particle<S>],
+    first: usize,
+    second: usize,
+) -> Option<Contact<S>> {
+    let rel_pos = particles[first].pos - particles[second].pos;
+    let radii = particles[first].radius + particles[second].radius;
+    let distance2 = rel_pos.magnitude2();
+    if distance2 >= radii * radii {
+        return None;
+    }
+    let distance = distance2.sqrt();
+    Some(Contact {
+        first,
+        second,
+        normal: if distance > S::zero() {
+            rel_pos / distance
+        } else {
+            Vector3::unit_z()
+        },
+        penetration: radii - distance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_particles(rng: &fastrand::Rng, count: usize, extent: f64) -> Vec<Particle<f64>> {
+        (0..count)
+            .map(|_| {
+                let coordinate = || (rng.f64() - 0.5) * extent;
+                let pos = Vector3::new(coordinate(), coordinate(), coordinate());
+                Particle::new(pos, Vector3::zero(), 0.05 + 0.2 * rng.f64())
+            })
+            .collect()
+    }
+
+    fn brute_force(particles: &[Particle<f64>]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for second in 0..particles.len() {
+            for first in 0..second {
+                if contact_between(particles, first, second).is_some() {
+                    pairs.push((first, second));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn finds_the_same_contacts_as_checking_all_pairs() {
+        let rng = fastrand::Rng::with_seed(7);
+        let mut world = CollisionWorld::new::<f64>(&[]);
+        for (count, extent) in [(0, 1.0), (1, 1.0), (50, 1.0), (300, 4.0), (300, 1000.0)] {
+            let particles = random_particles(&rng, count, extent);
+            world.rebuild(&particles);
+            let mut pairs: Vec<_> = world
+                .contacts(&particles)
+                .iter()
+                .map(|contact| (contact.first, contact.second))
+                .collect();
+            pairs.sort_unstable();
+            let mut expected = brute_force(&particles);
+            expected.sort_unstable();
+            assert_eq!(pairs, expected, "{count} particles in a cube of {extent}");
+        }
+    }
+
+    #[test]
+    fn handles_particles_in_negative_and_distant_cells() {
+        let particles = [
+            Particle::new(Vector3::new(-0.05, -0.05, -0.05), Vector3::zero(), 0.1),
+            Particle::new(Vector3::new(0.05, 0.05, 0.05), Vector3::zero(), 0.1),
+            Particle::new(Vector3::new(1e12, -1e12, 0.0), Vector3::zero(), 0.1),
+            Particle::new(Vector3::new(1e12, -1e12, 0.1), Vector3::zero(), 0.1),
+        ];
+        let world = CollisionWorld::new(&particles);
+        let pairs: Vec<_> = world
+            .contacts(&particles)
+            .iter()
+            .map(|contact| (contact.first, contact.second))
+            .collect();
+        assert!(pairs.contains(&(0, 1)));
+        assert!(pairs.contains(&(2, 3)));
+    }
+
+    #[test]
+    fn accels_match_checking_all_pairs() {
+        let rng = fastrand::Rng::with_seed(11);
+        let particles = random_particles(&rng, 200, 3.0);
+        let world = CollisionWorld::new(&particles);
+        let accels = world.accels_from_collisions(&particles);
+        for (i, particle) in particles.iter().enumerate() {
+            let expected = particles
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(Vector3::zero(), |sum, (_, other)| {
+                    sum + particle.accel_from_collision_with(other)
+                });
+            assert!((accels[i] - expected).magnitude() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::{scalar, CollisionWorld, Contact, Particle, PenaltyContact, DT};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+
+/// Something that takes part in a contact, identified by its index among the
+/// particles or colliders of a model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Body {
+    Particle(usize),
+    Collider(usize),
+}
+
+/// A report that two bodies touch, for models to pass on to controllers as
+/// touch sensors, failure signals and the like.
+#[derive(Clone, Copy, Debug)]
+pub struct ContactEvent<S = f32> {
+    pub first: Body,
+    pub second: Body,
+    /// Unit vector pointing from `second` towards `first`.
+    pub normal: Vector3<S>,
+    /// How far the two bodies overlap. Never negative.
+    pub penetration: S,
+    /// The impulse on `first` (the opposite acts on `second`) over one time
+    /// step at the current contact force.
+    pub impulse: Vector3<S>,
+}
+
+impl<S: BaseFloat> ContactEvent<S> {
+    /// Whether `body` takes part in this contact.
+    pub fn involves(&self, body: Body) -> bool {
+        self.first == body || self.second == body
+    }
+    /// Whether this is a contact between `a` and `b`, in either order.
+    pub fn is_between(&self, a: Body, b: Body) -> bool {
+        (self.first, self.second) == (a, b) || (self.first, self.second) == (b, a)
+    }
+}
+
+// The impulse of an acceleration acting on a (unit mass) particle for a step
+pub(super) fn impulse_of<S: BaseFloat>(accel: Vector3<S>) -> Vector3<S> {
+    accel * scalar(DT)
+}
+
+impl<S: BaseFloat> Particle<S> {
+    /// The contact reported alongside [`Particle::accel_from_collision_with`],
+    /// if the particles touch. `self` is the particle with index `index`.
+    pub fn collision_event_with(
+        &self,
+        index: usize,
+        other: &Self,
+        other_index: usize,
+    ) -> Option<ContactEvent<S>> {
+        let rel_pos = self.pos - other.pos;
+        let distance = rel_pos.magnitude();
+        let penetration = self.radius + other.radius - distance;
+        (penetration >= S::zero() && distance > S::zero()).then(|| ContactEvent {
+            first: Body::Particle(index),
+            second: Body::Particle(other_index),
+            normal: rel_pos / distance,
+            penetration,
+            impulse: impulse_of(self.accel_from_collision_with(other)),
+        })
+    }
+    /// The contact reported alongside [`Particle::accel_from_contact_with`],
+    /// if the particles touch. `self` is the particle with index `index`.
+    pub fn contact_event_with(
+        &self,
+        index: usize,
+        other: &Self,
+        other_index: usize,
+        contact: &PenaltyContact<S>,
+    ) -> Option<ContactEvent<S>> {
+        let rel_pos = self.pos - other.pos;
+        let distance = rel_pos.magnitude();
+        let penetration = self.radius + other.radius - distance;
+        (penetration >= S::zero() && distance > S::zero()).then(|| ContactEvent {
+            first: Body::Particle(index),
+            second: Body::Particle(other_index),
+            normal: rel_pos / distance,
+            penetration,
+            impulse: impulse_of(self.accel_from_contact_with(other, contact)),
+        })
+    }
+}
+
+impl CollisionWorld {
+    /// A [`ContactEvent`] for every touching pair, with the impulses of
+    /// [`CollisionWorld::accels_from_contacts`]. `particles` must be the slice
+    /// this broadphase was last built from.
+    pub fn contact_events<S: BaseFloat>(
+        &self,
+        particles: &[Particle<S>],
+        contact: &PenaltyContact<S>,
+    ) -> Vec<ContactEvent<S>> {
+        let mut events = Vec::new();
+        self.for_each_contact(
+            particles,
+            |Contact {
+                 first,
+                 second,
+                 normal,
+                 penetration,
+             }| {
+                events.push(ContactEvent {
+                    first: Body::Particle(first),
+                    second: Body::Particle(second),
+                    normal,
+                    penetration,
+                    impulse: impulse_of(
+                        particles[first].accel_from_contact_with(&particles[second], contact),
+                    ),
+                });
+            },
+        );
+        events
+    }
+}
@@ -0,0 +1,479 @@
+use super::{scalar, Particle, PenaltyContact, Spring, SpringSystem};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+
+/// A line of particles from `start` to `end`, each linked to the next by a
+/// stretch spring, and optionally to the one after that by a bend spring.
+///
+/// Springs get their rest length from the initial layout, so only the
+/// stiffness and damping of the given [`Spring`]s matter.
+///
+/// ```
+/// use agentbox::physics::{Rope, SpringSystem};
+/// use cgmath::Vector3;
+///
+/// // A rope hanging from a fixed point
+/// let rope = Rope::new(Vector3::new(0.0, 0.0, 3.0), Vector3::new(1.0, 0.0, 3.0), 10).pin_start();
+/// let system = SpringSystem::new()
+///     .rope(&rope)
+///     .gravity(Vector3::new(0.0, 0.0, -1.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rope<S = f32> {
+    start: Vector3<S>,
+    end: Vector3<S>,
+    segments: usize,
+    radius: S,
+    stretch: Spring<S>,
+    bend: Option<Spring<S>>,
+    pins: Vec<usize>,
+    self_collision: Option<PenaltyContact<S>>,
+}
+
+impl<S: BaseFloat> Rope<S> {
+    /// A thin, limp rope of `segments` springs.
+    ///
+    /// # Panics
+    ///
+    /// If `segments` is zero.
+    pub fn new(start: Vector3<S>, end: Vector3<S>, segments: usize) -> Self {
+        assert!(segments > 0, "a rope needs at least one segment");
+        let spacing = (end - start).magnitude() / scalar(segments);
+        Self {
+            start,
+            end,
+            segments,
+            radius: spacing / scalar(4.0),
+            stretch: Spring::UNIT_ROD.cast(),
+            bend: None,
+            pins: Vec::new(),
+            self_collision: None,
+        }
+    }
+    /// A chain of `links` stiff links, with particles large enough to touch.
+    pub fn chain(start: Vector3<S>, end: Vector3<S>, links: usize) -> Self {
+        let rope = Self::new(start, end, links);
+        let radius = rope.spacing() / scalar(2.0);
+        rope.with_radius(radius).with_stretch(Spring {
+            stiffness: scalar(3000.0),
+            damping: scalar(10.0),
+            rest_length: S::zero(),
+        })
+    }
+
+    #[must_use]
+    pub fn with_radius(self, radius: S) -> Self {
+        Self { radius, ..self }
+    }
+    #[must_use]
+    pub fn with_stretch(self, spring: Spring<S>) -> Self {
+        Self {
+            stretch: spring,
+            ..self
+        }
+    }
+    /// Resist bending with springs spanning two segments.
+    #[must_use]
+    pub fn with_bend(self, spring: Spring<S>) -> Self {
+        Self {
+            bend: Some(spring),
+            ..self
+        }
+    }
+    /// Let the particles collide with each other. This turns on
+    /// [`SpringSystem::particle_collisions`] for the whole system, and needs
+    /// particles no larger than half the spacing, so that neighbours do not
+    /// push each other apart.
+    ///
+    /// # Panics
+    ///
+    /// If the particles are larger than half the spacing.
+    #[must_use]
+    pub fn with_self_collision(self, contact: PenaltyContact<S>) -> Self {
+        assert!(
+            self.radius <= self.spacing() / scalar(2.0),
+            "self-colliding rope particles overlap their neighbours"
+        );
+        Self {
+            self_collision: Some(contact),
+            ..self
+        }
+    }
+    /// Hold particle `index`, counting from the start, in place.
+    ///
+    /// # Panics
+    ///
+    /// If there is no particle `index`.
+    #[must_use]
+    pub fn pin(mut self, index: usize) -> Self {
+        assert!(
+            index < self.particle_count(),
+            "pinning nonexistent rope particle {index}"
+        );
+        self.pins.push(index);
+        self
+    }
+    #[must_use]
+    pub fn pin_start(self) -> Self {
+        self.pin(0)
+    }
+    #[must_use]
+    pub fn pin_end(self) -> Self {
+        let last = self.particle_count() - 1;
+        self.pin(last)
+    }
+
+    /// The number of particles.
+    pub fn particle_count(&self) -> usize {
+        self.segments + 1
+    }
+    /// The distance between neighbouring particles at rest.
+    pub fn spacing(&self) -> S {
+        (self.end - self.start).magnitude() / scalar(self.segments)
+    }
+}
+
+/// A sheet of particles spanning `corner` to `corner + u + v`, with
+/// `resolution[0]` particles along `u` and `resolution[1]` along `v`.
+///
+/// Neighbours along the grid are linked by stretch springs, diagonal
+/// neighbours by shear springs and particles two apart by bend springs. Only
+/// stretch springs are drawn.
+///
+/// ```
+/// use agentbox::physics::{Cloth, SpringSystem};
+/// use cgmath::Vector3;
+///
+/// // A flag, held along its edge at the pole
+/// let mut flag = Cloth::new(Vector3::new(0.0, 0.0, 3.0), Vector3::unit_x(), -Vector3::unit_z(), [10, 8]);
+/// for j in 0..8 {
+///     flag = flag.pin(0, j);
+/// }
+/// let system = SpringSystem::new().cloth(&flag);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cloth<S = f32> {
+    corner: Vector3<S>,
+    u: Vector3<S>,
+    v: Vector3<S>,
+    resolution: [usize; 2],
+    radius: S,
+    stretch: Spring<S>,
+    shear: Option<Spring<S>>,
+    bend: Option<Spring<S>>,
+    pins: Vec<usize>,
+    self_collision: Option<PenaltyContact<S>>,
+}
+
+impl<S: BaseFloat> Cloth<S> {
+    /// A cloth that resists stretching and shearing, but bends freely.
+    ///
+    /// # Panics
+    ///
+    /// If `resolution` has fewer than two particles either way.
+    pub fn new(corner: Vector3<S>, u: Vector3<S>, v: Vector3<S>, resolution: [usize; 2]) -> Self {
+        assert!(
+            resolution[0] > 1 && resolution[1] > 1,
+            "a cloth needs at least two particles each way"
+        );
+        let mut cloth = Self {
+            corner,
+            u,
+            v,
+            resolution,
+            radius: S::zero(),
+            stretch: Spring::UNIT_ROD.cast(),
+            shear: None,
+            bend: None,
+            pins: Vec::new(),
+            self_collision: None,
+        };
+        let [du, dv] = cloth.spacing();
+        cloth.radius = du.min(dv) / scalar(4.0);
+        cloth.shear = Some(Spring {
+            stiffness: cloth.stretch.stiffness / scalar(4.0),
+            ..cloth.stretch
+        });
+        cloth
+    }
+
+    #[must_use]
+    pub fn with_radius(self, radius: S) -> Self {
+        Self { radius, ..self }
+    }
+    #[must_use]
+    pub fn with_stretch(self, spring: Spring<S>) -> Self {
+        Self {
+            stretch: spring,
+            ..self
+        }
+    }
+    #[must_use]
+    pub fn with_shear(self, spring: Option<Spring<S>>) -> Self {
+        Self {
+            shear: spring,
+            ..self
+        }
+    }
+    #[must_use]
+    pub fn with_bend(self, spring: Option<Spring<S>>) -> Self {
+        Self {
+            bend: spring,
+            ..self
+        }
+    }
+    /// Let the particles collide with each other, as with
+    /// [`Rope::with_self_collision`].
+    ///
+    /// # Panics
+    ///
+    /// If the particles are larger than half the smaller spacing.
+    #[must_use]
+    pub fn with_self_collision(self, contact: PenaltyContact<S>) -> Self {
+        let [du, dv] = self.spacing();
+        assert!(
+            self.radius <= du.min(dv) / scalar(2.0),
+            "self-colliding cloth particles overlap their neighbours"
+        );
+        Self {
+            self_collision: Some(contact),
+            ..self
+        }
+    }
+    /// Hold particle `(i, j)` in place.
+    ///
+    /// # Panics
+    ///
+    /// If there is no particle `(i, j)`.
+    #[must_use]
+    pub fn pin(mut self, i: usize, j: usize) -> Self {
+        let index = self.index(i, j);
+        self.pins.push(index);
+        self
+    }
+
+    /// The number of particles.
+    pub fn particle_count(&self) -> usize {
+        self.resolution[0] * self.resolution[1]
+    }
+    /// Where particle `(i, j)` comes among the cloth's particles, which are
+    /// laid out with `i` varying fastest.
+    ///
+    /// # Panics
+    ///
+    /// If there is no particle `(i, j)`.
+    pub fn index(&self, i: usize, j: usize) -> usize {
+        let [ni, nj] = self.resolution;
+        assert!(i < ni && j < nj, "nonexistent cloth particle ({i}, {j})");
+        j * ni + i
+    }
+    /// The distance between neighbouring particles at rest, along `u` and `v`.
+    pub fn spacing(&self) -> [S; 2] {
+        let [ni, nj] = self.resolution;
+        [
+            self.u.magnitude() / scalar(ni - 1),
+            self.v.magnitude() / scalar(nj - 1),
+        ]
+    }
+}
+
+impl<S: BaseFloat> SpringSystem<S> {
+    /// Add the particles of `rope` from start to end, followed by its
+    /// springs.
+    #[must_use]
+    pub fn rope(self, rope: &Rope<S>) -> Self {
+        let first = self.particle_count();
+        let step = (rope.end - rope.start) / scalar(rope.segments);
+        let mut system = (0..rope.particle_count()).fold(self, |system, i| {
+            let pos = rope.start + step * scalar(i);
+            system.particle(Particle::new(pos, Vector3::zero(), rope.radius))
+        });
+
+        let mut links = vec![(1, rope.stretch, true)];
+        links.extend(rope.bend.map(|bend| (2, bend, false)));
+        for (span, spring, visible) in links {
+            let rest_length = rope.spacing() * scalar(span);
+            for i in first..first + rope.particle_count().saturating_sub(span) {
+                system = system.link(i, i + span, spring, rest_length, visible);
+            }
+        }
+        system.finish_deformable(first, &rope.pins, rope.self_collision)
+    }
+
+    /// Add the particles of `cloth` in the order of [`Cloth::index`], followed
+    /// by its springs.
+    #[must_use]
+    pub fn cloth(self, cloth: &Cloth<S>) -> Self {
+        let first = self.particle_count();
+        let [ni, nj] = cloth.resolution;
+        let (du, dv) = (cloth.u / scalar(ni - 1), cloth.v / scalar(nj - 1));
+        let offset = |i: usize, j: usize| du * scalar(i) + dv * scalar(j);
+        let mut system =
+            (0..nj)
+                .flat_map(|j| (0..ni).map(move |i| (i, j)))
+                .fold(self, |system, (i, j)| {
+                    let pos = cloth.corner + offset(i, j);
+                    system.particle(Particle::new(pos, Vector3::zero(), cloth.radius))
+                });
+
+        // Each kind of spring, as pairs of offsets from a particle to the two
+        // it links
+        let mut links = vec![(
+            vec![((0, 0), (1, 0)), ((0, 0), (0, 1))],
+            cloth.stretch,
+            true,
+        )];
+        links.extend(
+            cloth
+                .shear
+                .map(|shear| (vec![((0, 0), (1, 1)), ((1, 0), (0, 1))], shear, false)),
+        );
+        links.extend(
+            cloth
+                .bend
+                .map(|bend| (vec![((0, 0), (2, 0)), ((0, 0), (0, 2))], bend, false)),
+        );
+        for (offsets, spring, visible) in links {
+            for ((ai, aj), (bi, bj)) in offsets {
+                for j in 0..nj.saturating_sub(aj.max(bj)) {
+                    for i in 0..ni.saturating_sub(ai.max(bi)) {
+                        let (a, b) = ((i + ai, j + aj), (i + bi, j + bj));
+                        let rest_length = (offset(b.0, b.1) - offset(a.0, a.1)).magnitude();
+                        system = system.link(
+                            first + cloth.index(a.0, a.1),
+                            first + cloth.index(b.0, b.1),
+                            spring,
+                            rest_length,
+                            visible,
+                        );
+                    }
+                }
+            }
+        }
+        system.finish_deformable(first, &cloth.pins, cloth.self_collision)
+    }
+
+    fn link(
+        self,
+        first: usize,
+        second: usize,
+        spring: Spring<S>,
+        rest_length: S,
+        visible: bool,
+    ) -> Self {
+        let spring = Spring {
+            rest_length,
+            ..spring
+        };
+        if visible {
+            self.spring(first, second, spring)
+        } else {
+            self.hidden_spring(first, second, spring)
+        }
+    }
+
+    // Pin and turn on collisions for a rope or cloth whose particles start at
+    // `first`
+    fn finish_deformable(
+        self,
+        first: usize,
+        pins: &[usize],
+        self_collision: Option<PenaltyContact<S>>,
+    ) -> Self {
+        let system = pins
+            .iter()
+            .fold(self, |system, &pin| system.pin(first + pin));
+        match self_collision {
+            Some(contact) => system.particle_collisions(contact),
+            None => system,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(system: &SpringSystem<f64>, steps: usize) -> Vec<Particle<f64>> {
+        let mut particles = system.initial_particles();
+        for step in 0..steps {
+            particles = system.step(&particles, scalar::<f64>(step) * crate::physics::DT);
+        }
+        particles
+    }
+
+    #[test]
+    fn hanging_chain_stretches_by_the_weight_below() {
+        let links = 10;
+        let top = Vector3::new(0.0, 0.0, 3.0);
+        let chain = Rope::chain(top, Vector3::new(0.0, 0.0, 2.0), links).pin_start();
+        let system = SpringSystem::new().rope(&chain).gravity(-Vector3::unit_z());
+        let particles = run(&system, 5000);
+
+        assert!((particles[0].pos - top).magnitude() < 1e-12);
+        assert!(particles[0].vel.magnitude() < 1e-12);
+        for (i, pair) in particles.windows(2).enumerate() {
+            // Each link holds up the (unit mass) particles below it
+            let below = scalar::<f64>(links - i);
+            let expected = chain.spacing() + below / 3000.0;
+            let length = (pair[1].pos - pair[0].pos).magnitude();
+            assert!(
+                (length - expected).abs() < 1e-4,
+                "link {i} is {length}, not {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn pinned_cloth_edge_stays_put() {
+        let mut flag = Cloth::new(
+            Vector3::new(0.0, 0.0, 3.0),
+            Vector3::unit_x(),
+            -Vector3::unit_z(),
+            [6, 5],
+        );
+        for j in 0..5 {
+            flag = flag.pin(0, j);
+        }
+        let system = SpringSystem::new()
+            .cloth(&flag)
+            .gravity(Vector3::new(0.0, 0.0, -9.81));
+        let start = system.initial_particles();
+        let particles = run(&system, 200);
+
+        for j in 0..5 {
+            let pin = flag.index(0, j);
+            assert!((particles[pin].pos - start[pin].pos).magnitude() < 1e-12);
+        }
+        let free = flag.index(5, 0);
+        assert!(particles[free].pos.z < start[free].pos.z - 0.5);
+    }
+
+    #[test]
+    fn self_colliding_ropes_rest_on_each_other() {
+        // A rope held at both ends, and a crossing one dropped onto it
+        let held = Rope::new(Vector3::new(0.0, 0.5, 1.0), Vector3::new(1.0, 0.5, 1.0), 10);
+        let dropped = Rope::new(Vector3::new(0.5, 0.0, 1.2), Vector3::new(0.5, 1.0, 1.2), 10);
+        let radius = held.spacing() / 2.0;
+        let system = |contact: Option<PenaltyContact<f64>>| {
+            let (mut held, mut dropped) = (
+                held.clone().with_radius(radius).pin_start().pin_end(),
+                dropped.clone().with_radius(radius),
+            );
+            if let Some(contact) = contact {
+                held = held.with_self_collision(contact);
+                dropped = dropped.with_self_collision(contact);
+            }
+            SpringSystem::new()
+                .rope(&held)
+                .rope(&dropped)
+                .gravity(Vector3::new(0.0, 0.0, -9.81))
+        };
+        // The middles of both ropes
+        let gap = |particles: &[Particle<f64>]| particles[16].pos.z - particles[5].pos.z;
+
+        let particles = run(&system(Some(PenaltyContact::DEFAULT.cast())), 100);
+        assert!(gap(&particles) > radius, "sank to {}", gap(&particles));
+        let particles = run(&system(None), 100);
+        assert!(gap(&particles) < 0.0);
+    }
+}
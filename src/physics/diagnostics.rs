@@ -0,0 +1,223 @@
+//! Conserved quantities of a particle set, for telling whether a model is
+//! numerically sound. Particles have unit mass, as everywhere in [`super`].
+
+use super::{scalar, Particle, Spring};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+use log::{info, warn};
+
+/// A snapshot of the energy and momentum of a particle set.
+#[derive(Clone, Copy, Debug)]
+pub struct Diagnostics<S = f32> {
+    pub kinetic_energy: S,
+    pub gravitational_energy: S,
+    pub spring_energy: S,
+    pub linear_momentum: Vector3<S>,
+    /// About the origin.
+    pub angular_momentum: Vector3<S>,
+}
+
+impl<S: BaseFloat> Diagnostics<S> {
+    /// Measure `particles` connected by `springs`, in a uniform gravitational
+    /// field with acceleration `gravity`.
+    pub fn measure<'a>(
+        particles: &[Particle<S>],
+        gravity: Vector3<S>,
+        springs: impl IntoIterator<Item = (usize, usize, &'a Spring<S>)>,
+    ) -> Self
+    where
+        S: 'a,
+    {
+        Self {
+            kinetic_energy: kinetic_energy(particles),
+            gravitational_energy: gravitational_energy(particles, gravity),
+            spring_energy: spring_energy(particles, springs),
+            linear_momentum: linear_momentum(particles),
+            angular_momentum: angular_momentum(particles, Vector3::zero()),
+        }
+    }
+    pub fn total_energy(&self) -> S {
+        self.kinetic_energy + self.gravitational_energy + self.spring_energy
+    }
+    pub fn cast<T: BaseFloat>(&self) -> Diagnostics<T> {
+        Diagnostics {
+            kinetic_energy: scalar(self.kinetic_energy),
+            gravitational_energy: scalar(self.gravitational_energy),
+            spring_energy: scalar(self.spring_energy),
+            linear_momentum: self.linear_momentum.map(scalar),
+            angular_momentum: self.angular_momentum.map(scalar),
+        }
+    }
+}
+
+pub fn kinetic_energy<S: BaseFloat>(particles: &[Particle<S>]) -> S {
+    particles
+        .iter()
+        .map(|p| p.vel.magnitude2() / scalar::<S>(2.0))
+        .fold(S::zero(), |a, b| a + b)
+}
+
+/// Potential energy relative to the origin, in a uniform field with
+/// acceleration `gravity`.
+pub fn gravitational_energy<S: BaseFloat>(particles: &[Particle<S>], gravity: Vector3<S>) -> S {
+    particles
+        .iter()
+        .map(|p| -gravity.dot(p.pos))
+        .fold(S::zero(), |a, b| a + b)
+}
+
+/// Elastic energy stored in `springs`, each connecting two particles by index.
+pub fn spring_energy<'a, S: BaseFloat + 'a>(
+    particles: &[Particle<S>],
+    springs: impl IntoIterator<Item = (usize, usize, &'a Spring<S>)>,
+) -> S {
+    springs
+        .into_iter()
+        .map(|(first, second, spring)| {
+            let extension =
+                (particles[first].pos - particles[second].pos).magnitude() - spring.rest_length;
+            spring.stiffness * extension * extension / scalar::<S>(2.0)
+        })
+        .fold(S::zero(), |a, b| a + b)
+}
+
+pub fn linear_momentum<S: BaseFloat>(particles: &[Particle<S>]) -> Vector3<S> {
+    particles.iter().map(|p| p.vel).sum()
+}
+
+pub fn angular_momentum<S: BaseFloat>(particles: &[Particle<S>], about: Vector3<S>) -> Vector3<S> {
+    particles.iter().map(|p| (p.pos - about).cross(p.vel)).sum()
+}
+
+// How often to log the drift, in observations
+const LOG_INTERVAL: u64 = 1000;
+
+/// Tracks how the total energy drifts from its initial value, logging it
+/// periodically and warning once it has grown beyond a threshold.
+pub struct DriftMonitor {
+    initial: Option<Diagnostics<f64>>,
+    observations: u64,
+    max_energy_growth: f64,
+    warned: bool,
+}
+
+impl DriftMonitor {
+    /// Warn when the total energy exceeds its initial value by more than
+    /// `max_energy_growth`, relative to the initial magnitude.
+    #[must_use]
+    pub fn new(max_energy_growth: f32) -> Self {
+        Self {
+            initial: None,
+            observations: 0,
+            max_energy_growth: f64::from(max_energy_growth),
+            warned: false,
+        }
+    }
+
+    /// The relative energy growth since the first observation.
+    pub fn observe<S: BaseFloat>(&mut self, current: &Diagnostics<S>) -> f64 {
+        let current = current.cast::<f64>();
+        let initial = *self.initial.get_or_insert(current);
+        let initial_energy = initial.total_energy();
+        let growth =
+            (current.total_energy() - initial_energy) / initial_energy.abs().max(f64::EPSILON);
+
+        if self.observations % LOG_INTERVAL == 0 {
+            info!(
+                "energy drift {:+.3}% after {} ticks (momentum drift {:?}, angular momentum drift {:?})",
+                growth * 100.0,
+                self.observations,
+                current.linear_momentum - initial.linear_momentum,
+                current.angular_momentum - initial.angular_momentum,
+            );
+        }
+        if growth > self.max_energy_growth && !self.warned {
+            self.warned = true;
+            warn!(
+                "energy grew by {:.1}% after {} ticks, beyond the allowed {:.1}%",
+                growth * 100.0,
+                self.observations,
+                self.max_energy_growth * 100.0,
+            );
+        }
+        self.observations += 1;
+        growth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::BouncingBalls, physics::time_step_with_rk4, Model};
+
+    const SPRING: Spring<f64> = Spring {
+        stiffness: 10.0,
+        damping: 0.0,
+        rest_length: 1.0,
+    };
+
+    // Two particles on an undamped spring, stretched and set spinning
+    fn oscillator() -> Vec<Particle<f64>> {
+        vec![
+            Particle::new(Vector3::zero(), -Vector3::unit_y(), 0.1),
+            Particle::new(Vector3::unit_x() * 1.5, Vector3::unit_y(), 0.1),
+        ]
+    }
+
+    fn measure(particles: &[Particle<f64>]) -> Diagnostics<f64> {
+        Diagnostics::measure(particles, Vector3::zero(), [(0, 1, &SPRING)])
+    }
+
+    fn step(particles: &[Particle<f64>]) -> Vec<Particle<f64>> {
+        time_step_with_rk4(particles, &(), |particles, _| {
+            vec![
+                particles[0].accel_from_spring_to(&particles[1], &SPRING),
+                particles[1].accel_from_spring_to(&particles[0], &SPRING),
+            ]
+        })
+    }
+
+    #[test]
+    fn conservative_oscillator_stays_under_the_threshold() {
+        let mut monitor = DriftMonitor::new(1e-3);
+        let mut particles = oscillator();
+        for _ in 0..2000 {
+            let growth = monitor.observe(&measure(&particles));
+            assert!(growth.abs() < 1e-4, "energy drifted by {growth}");
+            particles = step(&particles);
+        }
+        assert!(!monitor.warned);
+        assert!(measure(&particles).linear_momentum.magnitude() < 1e-12);
+    }
+
+    #[test]
+    fn injecting_energy_trips_the_monitor() {
+        let mut monitor = DriftMonitor::new(0.05);
+        let mut particles = oscillator();
+        let mut tripped_after = None;
+        for tick in 0..100 {
+            monitor.observe(&measure(&particles));
+            if monitor.warned {
+                tripped_after = tripped_after.or(Some(tick));
+            }
+            particles = step(&particles);
+            for particle in &mut particles {
+                particle.vel *= 1.01;
+            }
+        }
+        // Kinetic energy, under half of the total, grows by 2% a tick
+        assert!(matches!(tripped_after, Some(3..=10)), "{tripped_after:?}");
+    }
+
+    #[test]
+    fn bouncing_balls_do_not_gain_energy() {
+        let mut monitor = DriftMonitor::new(1e-3);
+        let mut world = BouncingBalls::new_world();
+        let signals = BouncingBalls::new_signals();
+        for _ in 0..500 {
+            let diagnostics = BouncingBalls::diagnostics(&world).unwrap();
+            assert!(monitor.observe(&diagnostics) < 1e-3);
+            BouncingBalls::update(&mut world, &signals);
+        }
+        assert!(!monitor.warned);
+    }
+}
@@ -0,0 +1,196 @@
+use super::{scalar, Particle};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+
+/// An acceleration acting on every particle, depending on where it is, how it
+/// moves and the simulated time.
+///
+/// Fields compose: tuples, slices and `Vec`s of fields are fields themselves,
+/// summing their members.
+pub trait ForceField<S: BaseFloat = f32> {
+    fn accel(&self, particle: &Particle<S>, time: S) -> Vector3<S>;
+
+    /// The acceleration of every particle.
+    fn accels(&self, particles: &[Particle<S>], time: S) -> Vec<Vector3<S>> {
+        particles
+            .iter()
+            .map(|particle| self.accel(particle, time))
+            .collect()
+    }
+}
+
+/// A uniform gravitational field.
+#[derive(Clone, Copy, Debug)]
+pub struct Gravity<S = f32> {
+    pub accel: Vector3<S>,
+}
+
+impl Gravity {
+    /// Standard gravity, along negative z.
+    pub const EARTH: Self = Self {
+        accel: Vector3::new(0.0, 0.0, -9.81),
+    };
+    /// Gravity at the surface of the moon, along negative z.
+    pub const MOON: Self = Self {
+        accel: Vector3::new(0.0, 0.0, -1.62),
+    };
+}
+
+impl<S: BaseFloat> Gravity<S> {
+    /// Gravity of magnitude `g`, along negative z.
+    pub fn down(g: S) -> Self {
+        Self {
+            accel: Vector3::unit_z() * -g,
+        }
+    }
+}
+
+impl<S: BaseFloat> ForceField<S> for Gravity<S> {
+    fn accel(&self, _particle: &Particle<S>, _time: S) -> Vector3<S> {
+        self.accel
+    }
+}
+
+/// Viscous drag, proportional to the velocity. Dominates for small, slow
+/// things.
+#[derive(Clone, Copy, Debug)]
+pub struct LinearDrag<S = f32> {
+    pub coefficient: S,
+}
+
+impl<S: BaseFloat> ForceField<S> for LinearDrag<S> {
+    fn accel(&self, particle: &Particle<S>, _time: S) -> Vector3<S> {
+        particle.vel * -self.coefficient
+    }
+}
+
+/// Air resistance, proportional to the square of the speed. Dominates for
+/// large, fast things.
+#[derive(Clone, Copy, Debug)]
+pub struct QuadraticDrag<S = f32> {
+    pub coefficient: S,
+}
+
+impl<S: BaseFloat> ForceField<S> for QuadraticDrag<S> {
+    fn accel(&self, particle: &Particle<S>, _time: S) -> Vector3<S> {
+        particle.vel * (-self.coefficient * particle.vel.magnitude())
+    }
+}
+
+// The number of sinusoids summed into the gusts of a `Wind`
+const GUST_MODES: usize = 4;
+// The range of gust angular frequencies, in radians per unit time
+const GUST_MIN_FREQUENCY: f64 = 0.2;
+const GUST_MAX_FREQUENCY: f64 = 2.0;
+
+/// Horizontally gusting wind, dragging particles towards the air velocity.
+///
+/// Gusts are a sum of sinusoids with directions, frequencies and phases drawn
+/// from a seed, so the same seed always blows the same way.
+#[derive(Clone, Copy, Debug)]
+pub struct Wind<S = f32> {
+    /// The air velocity without gusts.
+    pub mean: Vector3<S>,
+    /// How strongly particles are dragged towards the air velocity.
+    pub drag: S,
+    gusts: [Gust<S>; GUST_MODES],
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Gust<S> {
+    amplitude: Vector3<S>,
+    frequency: S,
+    phase: S,
+}
+
+impl<S: BaseFloat> Wind<S> {
+    /// Wind blowing at `mean`, with gusts of typical speed `gust_speed`.
+    pub fn new(mean: Vector3<S>, drag: S, gust_speed: S, seed: u64) -> Self {
+        let rng = fastrand::Rng::with_seed(seed);
+        let tau = std::f64::consts::TAU;
+        // Equal modes of this amplitude have an RMS speed of `gust_speed`
+        let amplitude = gust_speed * (scalar::<S>(2.0) / scalar(GUST_MODES)).sqrt();
+        let gusts = [(); GUST_MODES].map(|()| {
+            let direction = tau * rng.f64();
+            Gust {
+                amplitude: Vector3::new(
+                    scalar(direction.cos()),
+                    scalar(direction.sin()),
+                    S::zero(),
+                ) * amplitude,
+                frequency: scalar(
+                    GUST_MIN_FREQUENCY + (GUST_MAX_FREQUENCY - GUST_MIN_FREQUENCY) * rng.f64(),
+                ),
+                phase: scalar(tau * rng.f64()),
+            }
+        });
+        Self { mean, drag, gusts }
+    }
+
+    /// The air velocity at `time`.
+    pub fn velocity(&self, time: S) -> Vector3<S> {
+        self.gusts.iter().fold(self.mean, |velocity, gust| {
+            velocity + gust.amplitude * (gust.frequency * time + gust.phase).sin()
+        })
+    }
+}
+
+impl<S: BaseFloat> ForceField<S> for Wind<S> {
+    fn accel(&self, particle: &Particle<S>, time: S) -> Vector3<S> {
+        (self.velocity(time) - particle.vel) * self.drag
+    }
+}
+
+/// An inverse-square attraction towards `center`, or repulsion for negative
+/// `strength`.
+#[derive(Clone, Copy, Debug)]
+pub struct PointAttractor<S = f32> {
+    pub center: Vector3<S>,
+    pub strength: S,
+    /// Spreads the attractor over this distance, keeping the acceleration
+    /// finite at the center.
+    pub softening: S,
+}
+
+impl<S: BaseFloat> ForceField<S> for PointAttractor<S> {
+    fn accel(&self, particle: &Particle<S>, _time: S) -> Vector3<S> {
+        let rel_pos = self.center - particle.pos;
+        let distance2 = rel_pos.magnitude2() + self.softening * self.softening;
+        rel_pos * (self.strength / (distance2 * distance2.sqrt()))
+    }
+}
+
+impl<S: BaseFloat, F: ForceField<S>> ForceField<S> for [F] {
+    fn accel(&self, particle: &Particle<S>, time: S) -> Vector3<S> {
+        self.iter().map(|field| field.accel(particle, time)).sum()
+    }
+}
+
+impl<S: BaseFloat, F: ForceField<S>> ForceField<S> for Vec<F> {
+    fn accel(&self, particle: &Particle<S>, time: S) -> Vector3<S> {
+        self.as_slice().accel(particle, time)
+    }
+}
+
+impl<S: BaseFloat, F: ForceField<S> + ?Sized> ForceField<S> for Box<F> {
+    fn accel(&self, particle: &Particle<S>, time: S) -> Vector3<S> {
+        (**self).accel(particle, time)
+    }
+}
+
+macro_rules! impl_force_field_for_tuple {
+    ($($field:ident),+) => {
+        impl<S: BaseFloat, $($field: ForceField<S>),+> ForceField<S> for ($($field,)+) {
+            #[allow(non_snake_case)]
+            fn accel(&self, particle: &Particle<S>, time: S) -> Vector3<S> {
+                let ($($field,)+) = self;
+                Vector3::zero() $(+ $field.accel(particle, time))+
+            }
+        }
+    };
+}
+
+impl_force_field_for_tuple!(A);
+impl_force_field_for_tuple!(A, B);
+impl_force_field_for_tuple!(A, B, C);
+impl_force_field_for_tuple!(A, B, C, D);
+impl_force_field_for_tuple!(A, B, C, D, E);
@@ -0,0 +1,407 @@
+use super::{scalar, Particle};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+
+// One-sided penalty keeping a joint coordinate within its limits
+const LIMIT_STIFFNESS: f64 = 500.0;
+const LIMIT_DAMPING: f64 = 10.0;
+// Penalty keeping a hinge planar or a slider on its axis
+const ALIGNMENT_STIFFNESS: f64 = 1000.0;
+const ALIGNMENT_DAMPING: f64 = 4.0;
+// How hard a velocity motor pushes per unit of velocity error
+const MOTOR_GAIN: f64 = 20.0;
+
+/// A joint between particles, with a single coordinate: an angle for hinges
+/// and ball joints, a displacement for prismatic joints.
+///
+/// Links are kept at length by [`super::Spring`]s. The joint adds the forces
+/// that keep it aligned and within its limits, and those of its motor.
+pub trait Joint<S: BaseFloat = f32> {
+    /// The joint angle (radians) or position.
+    fn position(&self, particles: &[Particle<S>]) -> S;
+    /// The time derivative of [`Joint::position`].
+    fn velocity(&self, particles: &[Particle<S>]) -> S;
+    fn set_motor(&mut self, motor: Option<Motor<S>>);
+    /// Add the accelerations caused by this joint to `accels`.
+    fn add_accels(&self, particles: &[Particle<S>], accels: &mut [Vector3<S>]);
+}
+
+/// Bounds on a joint coordinate.
+#[derive(Clone, Copy, Debug)]
+pub struct JointLimits<S = f32> {
+    pub lower: S,
+    pub upper: S,
+}
+
+impl<S: BaseFloat> JointLimits<S> {
+    // The generalized force pushing the joint back within its limits
+    fn effort(&self, position: S, velocity: S) -> S {
+        let violation = if position < self.lower {
+            position - self.lower
+        } else if position > self.upper {
+            position - self.upper
+        } else {
+            return S::zero();
+        };
+        -scalar::<S>(LIMIT_STIFFNESS) * violation - scalar::<S>(LIMIT_DAMPING) * velocity
+    }
+}
+
+/// Drives a joint coordinate. Effort is torque for angular joints and force
+/// for prismatic joints.
+#[derive(Clone, Copy, Debug)]
+pub enum Motor<S = f32> {
+    /// Apply a constant effort.
+    Effort(S),
+    /// Track a target velocity, using at most `max_effort`.
+    Velocity { target: S, max_effort: S },
+}
+
+impl<S: BaseFloat> Motor<S> {
+    fn effort(&self, velocity: S) -> S {
+        match *self {
+            Motor::Effort(effort) => effort,
+            Motor::Velocity { target, max_effort } => (scalar::<S>(MOTOR_GAIN)
+                * (target - velocity))
+                .max(-max_effort)
+                .min(max_effort),
+        }
+    }
+}
+
+fn total_effort<S: BaseFloat>(
+    limits: Option<&JointLimits<S>>,
+    motor: Option<&Motor<S>>,
+    position: S,
+    velocity: S,
+) -> S {
+    limits.map_or(S::zero(), |limits| limits.effort(position, velocity))
+        + motor.map_or(S::zero(), |motor| motor.effort(velocity))
+}
+
+// Apply `torque` about the unit `axis` between the links `parent -> pivot` and
+// `pivot -> child`, positive torque turning the child positively about `axis`.
+// Linear momentum is conserved by letting the pivot take up the difference.
+fn add_torque<S: BaseFloat>(
+    [parent, pivot, child]: [usize; 3],
+    particles: &[Particle<S>],
+    axis: Vector3<S>,
+    torque: S,
+    accels: &mut [Vector3<S>],
+) {
+    if torque == S::zero() {
+        return;
+    }
+    let to_child = particles[child].pos - particles[pivot].pos;
+    let from_parent = particles[pivot].pos - particles[parent].pos;
+    let on_child = axis.cross(to_child) * (torque / to_child.magnitude2());
+    let on_parent = axis.cross(from_parent) * (torque / from_parent.magnitude2());
+    accels[child] += on_child;
+    accels[parent] += on_parent;
+    accels[pivot] -= on_child + on_parent;
+}
+
+// The angular velocity about `axis` of the link from `from` to `to`
+fn angular_vel<S: BaseFloat>(from: &Particle<S>, to: &Particle<S>, axis: Vector3<S>) -> S {
+    let rel_pos = to.pos - from.pos;
+    axis.dot(rel_pos.cross(to.vel - from.vel)) / rel_pos.magnitude2()
+}
+
+/// A hinge at `pivot` with a fixed world-space `axis`, between the links
+/// `parent -> pivot` and `pivot -> child`. The angle is zero when the links
+/// are aligned and grows as the child turns positively about the axis.
+#[derive(Clone, Copy, Debug)]
+pub struct HingeJoint<S = f32> {
+    pub parent: usize,
+    pub pivot: usize,
+    pub child: usize,
+    pub axis: Vector3<S>,
+    pub limits: Option<JointLimits<S>>,
+    pub motor: Option<Motor<S>>,
+}
+
+impl<S: BaseFloat> HingeJoint<S> {
+    #[must_use]
+    pub fn new(parent: usize, pivot: usize, child: usize, axis: Vector3<S>) -> Self {
+        Self {
+            parent,
+            pivot,
+            child,
+            axis: axis.normalize(),
+            limits: None,
+            motor: None,
+        }
+    }
+    #[must_use]
+    pub fn with_limits(self, lower: S, upper: S) -> Self {
+        Self {
+            limits: Some(JointLimits { lower, upper }),
+            ..self
+        }
+    }
+}
+
+impl<S: BaseFloat> Joint<S> for HingeJoint<S> {
+    fn position(&self, particles: &[Particle<S>]) -> S {
+        let from_parent = particles[self.pivot].pos - particles[self.parent].pos;
+        let to_child = particles[self.child].pos - particles[self.pivot].pos;
+        let sin = self.axis.dot(from_parent.cross(to_child));
+        let cos = from_parent.dot(to_child);
+        sin.atan2(cos)
+    }
+    fn velocity(&self, particles: &[Particle<S>]) -> S {
+        let [parent, pivot, child] = [self.parent, self.pivot, self.child].map(|i| &particles[i]);
+        angular_vel(pivot, child, self.axis) - angular_vel(parent, pivot, self.axis)
+    }
+    fn set_motor(&mut self, motor: Option<Motor<S>>) {
+        self.motor = motor;
+    }
+    fn add_accels(&self, particles: &[Particle<S>], accels: &mut [Vector3<S>]) {
+        let (position, velocity) = (self.position(particles), self.velocity(particles));
+        let torque = total_effort(
+            self.limits.as_ref(),
+            self.motor.as_ref(),
+            position,
+            velocity,
+        );
+        add_torque(
+            [self.parent, self.pivot, self.child],
+            particles,
+            self.axis,
+            torque,
+            accels,
+        );
+
+        // Keep the child in the plane of rotation
+        let (pivot, child) = (&particles[self.pivot], &particles[self.child]);
+        let off_plane = self.axis.dot(child.pos - pivot.pos);
+        let off_plane_vel = self.axis.dot(child.vel - pivot.vel);
+        let align = self.axis
+            * (-scalar::<S>(ALIGNMENT_STIFFNESS) * off_plane
+                - scalar::<S>(ALIGNMENT_DAMPING) * off_plane_vel);
+        accels[self.child] += align;
+        accels[self.pivot] -= align;
+    }
+}
+
+/// A ball-and-socket joint at `pivot`, between the links `parent -> pivot`
+/// and `pivot -> child`. The angle is the bend between the links, from zero
+/// when aligned to pi when folded back, so limits make a cone.
+#[derive(Clone, Copy, Debug)]
+pub struct BallJoint<S = f32> {
+    pub parent: usize,
+    pub pivot: usize,
+    pub child: usize,
+    pub limits: Option<JointLimits<S>>,
+    pub motor: Option<Motor<S>>,
+}
+
+impl<S: BaseFloat> BallJoint<S> {
+    #[must_use]
+    pub fn new(parent: usize, pivot: usize, child: usize) -> Self {
+        Self {
+            parent,
+            pivot,
+            child,
+            limits: None,
+            motor: None,
+        }
+    }
+    #[must_use]
+    pub fn with_limits(self, lower: S, upper: S) -> Self {
+        Self {
+            limits: Some(JointLimits { lower, upper }),
+            ..self
+        }
+    }
+    // The axis about which the child bends away from the parent, if any
+    fn bend_axis(&self, particles: &[Particle<S>]) -> Option<Vector3<S>> {
+        let from_parent = particles[self.pivot].pos - particles[self.parent].pos;
+        let to_child = particles[self.child].pos - particles[self.pivot].pos;
+        let normal = from_parent.cross(to_child);
+        let magnitude = normal.magnitude();
+        (magnitude > S::zero()).then(|| normal / magnitude)
+    }
+}
+
+impl<S: BaseFloat> Joint<S> for BallJoint<S> {
+    fn position(&self, particles: &[Particle<S>]) -> S {
+        let from_parent = particles[self.pivot].pos - particles[self.parent].pos;
+        let to_child = particles[self.child].pos - particles[self.pivot].pos;
+        from_parent.angle(to_child).0
+    }
+    fn velocity(&self, particles: &[Particle<S>]) -> S {
+        let [parent, pivot, child] = [self.parent, self.pivot, self.child].map(|i| &particles[i]);
+        self.bend_axis(particles).map_or(S::zero(), |axis| {
+            angular_vel(pivot, child, axis) - angular_vel(parent, pivot, axis)
+        })
+    }
+    fn set_motor(&mut self, motor: Option<Motor<S>>) {
+        self.motor = motor;
+    }
+    fn add_accels(&self, particles: &[Particle<S>], accels: &mut [Vector3<S>]) {
+        if let Some(axis) = self.bend_axis(particles) {
+            let (position, velocity) = (self.position(particles), self.velocity(particles));
+            let torque = total_effort(
+                self.limits.as_ref(),
+                self.motor.as_ref(),
+                position,
+                velocity,
+            );
+            add_torque(
+                [self.parent, self.pivot, self.child],
+                particles,
+                axis,
+                torque,
+                accels,
+            );
+        }
+    }
+}
+
+/// A slider letting `slider` move along the world-space `axis` through
+/// `anchor`. The position is the displacement along the axis.
+#[derive(Clone, Copy, Debug)]
+pub struct PrismaticJoint<S = f32> {
+    pub anchor: usize,
+    pub slider: usize,
+    pub axis: Vector3<S>,
+    pub limits: Option<JointLimits<S>>,
+    pub motor: Option<Motor<S>>,
+}
+
+impl<S: BaseFloat> PrismaticJoint<S> {
+    #[must_use]
+    pub fn new(anchor: usize, slider: usize, axis: Vector3<S>) -> Self {
+        Self {
+            anchor,
+            slider,
+            axis: axis.normalize(),
+            limits: None,
+            motor: None,
+        }
+    }
+    #[must_use]
+    pub fn with_limits(self, lower: S, upper: S) -> Self {
+        Self {
+            limits: Some(JointLimits { lower, upper }),
+            ..self
+        }
+    }
+}
+
+impl<S: BaseFloat> Joint<S> for PrismaticJoint<S> {
+    fn position(&self, particles: &[Particle<S>]) -> S {
+        self.axis
+            .dot(particles[self.slider].pos - particles[self.anchor].pos)
+    }
+    fn velocity(&self, particles: &[Particle<S>]) -> S {
+        self.axis
+            .dot(particles[self.slider].vel - particles[self.anchor].vel)
+    }
+    fn set_motor(&mut self, motor: Option<Motor<S>>) {
+        self.motor = motor;
+    }
+    fn add_accels(&self, particles: &[Particle<S>], accels: &mut [Vector3<S>]) {
+        let (anchor, slider) = (&particles[self.anchor], &particles[self.slider]);
+        let rel_pos = slider.pos - anchor.pos;
+        let rel_vel = slider.vel - anchor.vel;
+        let (position, velocity) = (self.axis.dot(rel_pos), self.axis.dot(rel_vel));
+        let force = total_effort(
+            self.limits.as_ref(),
+            self.motor.as_ref(),
+            position,
+            velocity,
+        );
+
+        // Keep the slider on the axis
+        let off_axis = rel_pos - self.axis * position;
+        let off_axis_vel = rel_vel - self.axis * velocity;
+        let align = off_axis * -scalar::<S>(ALIGNMENT_STIFFNESS)
+            - off_axis_vel * scalar::<S>(ALIGNMENT_DAMPING);
+
+        accels[self.slider] += self.axis * force + align;
+        accels[self.anchor] -= self.axis * force + align;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{Spring, SpringSystem};
+
+    const STEPS: u32 = 2000;
+
+    fn simulate(system: &SpringSystem<f64>) -> Vec<Particle<f64>> {
+        let mut particles = system.initial_particles();
+        for step in 0..STEPS {
+            particles = system.step(&particles, f64::from(step) * crate::physics::DT);
+        }
+        particles
+    }
+
+    #[test]
+    fn hinge_keeps_the_child_in_its_plane() {
+        // The motor spins the child around the x axis, while gravity and the
+        // initial velocity pull it out of the yz plane
+        let hinge = HingeJoint {
+            motor: Some(Motor::Velocity {
+                target: 1.0,
+                max_effort: 10.0,
+            }),
+            ..HingeJoint::new(0, 1, 2, Vector3::unit_x())
+        };
+        let system = SpringSystem::new()
+            .particle(Particle::new(-Vector3::unit_z(), Vector3::zero(), 0.1))
+            .particle(Particle::new(Vector3::zero(), Vector3::zero(), 0.1))
+            .particle(Particle::new(Vector3::unit_y(), Vector3::unit_x(), 0.1))
+            .pin(0)
+            .pin(1)
+            .spring(1, 2, Spring::UNIT_ROD.cast())
+            .joint(hinge)
+            .gravity(Vector3::new(0.5, 0.0, 0.0));
+        let particles = simulate(&system);
+        let child = particles[2];
+        assert!(child.pos.x.abs() < 0.01, "drifted to {:?}", child.pos);
+        assert!((child.pos.magnitude() - 1.0).abs() < 0.01);
+        let velocity = system.joint_velocities(&particles)[0];
+        assert!((velocity - 1.0).abs() < 0.01, "turns at {velocity}");
+    }
+
+    #[test]
+    fn prismatic_joint_keeps_the_slider_on_its_axis_and_within_limits() {
+        let system = SpringSystem::new()
+            .particle(Particle::new(Vector3::zero(), Vector3::zero(), 0.1))
+            .particle(Particle::new(Vector3::unit_z(), Vector3::unit_y(), 0.1))
+            .pin(0)
+            .joint(PrismaticJoint::new(0, 1, Vector3::unit_z()).with_limits(-0.5, 2.0))
+            .gravity(Vector3::new(1.0, 0.0, -1.0));
+        let particles = simulate(&system);
+        let slider = particles[1];
+        assert!(
+            slider.pos.truncate().magnitude() < 0.01,
+            "drifted to {:?}",
+            slider.pos
+        );
+        let position = system.joint_positions(&particles)[0];
+        assert!((position + 0.5).abs() < 0.01, "rests at {position}");
+        assert!(system.joint_velocities(&particles)[0].abs() < 0.01);
+    }
+
+    #[test]
+    fn ball_joint_stays_within_its_cone() {
+        let limit = 0.5;
+        let system = SpringSystem::new()
+            .particle(Particle::new(-Vector3::unit_z(), Vector3::zero(), 0.1))
+            .particle(Particle::new(Vector3::zero(), Vector3::zero(), 0.1))
+            .particle(Particle::new(Vector3::unit_z(), Vector3::zero(), 0.1))
+            .pin(0)
+            .pin(1)
+            .spring(1, 2, Spring::UNIT_ROD.cast())
+            .joint(BallJoint::new(0, 1, 2).with_limits(0.0, limit))
+            .gravity(Vector3::new(1.0, 1.0, 0.0));
+        let particles = simulate(&system);
+        let bend = system.joint_positions(&particles)[0];
+        assert!((bend - limit).abs() < 0.05, "bent to {bend}");
+        assert!((particles[2].pos.magnitude() - 1.0).abs() < 0.01);
+    }
+}
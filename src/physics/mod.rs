@@ -1,56 +1,119 @@
 //! A toolbox for implementing the update part of a model.
 
-use cgmath::{prelude::*, Vector3};
+pub mod diagnostics;
 
-const DT: f32 = 0.01;
+mod colliders;
+mod collision_world;
+mod contact_events;
+mod deformables;
+mod force_fields;
+mod joints;
+mod particle_set;
+mod rigid_body;
+mod sph;
+mod spring_system;
 
-#[derive(Copy, Clone)]
-pub struct Particle {
-    pub pos: Vector3<f32>,
-    pub vel: Vector3<f32>,
-    pub radius: f32,
+pub use colliders::{
+    Capsule, Collider, ContactMaterial, Cuboid, Cylinder, PenaltyContact, Plane, Sphere,
+};
+pub use collision_world::{CollisionWorld, Contact};
+pub use contact_events::{Body, ContactEvent};
+pub use deformables::{Cloth, Rope};
+pub use force_fields::{ForceField, Gravity, LinearDrag, PointAttractor, QuadraticDrag, Wind};
+pub use joints::{BallJoint, HingeJoint, Joint, JointLimits, Motor, PrismaticJoint};
+pub use particle_set::{par_accelerations, step_with_rk4, ParticleSet, ParticleView, Rk4Scratch};
+pub use rigid_body::{RigidBody, Wrench};
+pub use sph::SphFluid;
+pub use spring_system::{SpringSystem, SpringSystemStyle};
+
+use cgmath::{num_traits::ToPrimitive, prelude::*, BaseFloat, Vector3};
+
+/// The time step of [`time_step_with_rk4`].
+pub const DT: f64 = 0.01;
+
+/// [`DT`] in the scalar type in use.
+#[must_use]
+pub fn dt<S: BaseFloat>() -> S {
+    scalar(DT)
+}
+
+// Convert a constant or a count to the scalar type in use
+pub(crate) fn scalar<S: BaseFloat>(x: impl ToPrimitive) -> S {
+    S::from(x).unwrap()
 }
-impl Particle {
-    pub fn new(pos: Vector3<f32>, vel: Vector3<f32>, radius: f32) -> Self {
+
+/// A point mass. The physics is generic over the scalar type, so models may
+/// simulate in `f64` and convert to `f32` only for their [`crate::Solid`]s.
+#[derive(Copy, Clone, Debug)]
+pub struct Particle<S = f32> {
+    pub pos: Vector3<S>,
+    pub vel: Vector3<S>,
+    pub radius: S,
+}
+impl<S: BaseFloat> Particle<S> {
+    pub fn new(pos: Vector3<S>, vel: Vector3<S>, radius: S) -> Self {
         Self { pos, vel, radius }
     }
-    pub fn accel_from_spring_to(&self, other: &Particle, spring: &Spring) -> Vector3<f32> {
+    pub fn cast<T: BaseFloat>(&self) -> Particle<T> {
+        Particle {
+            pos: self.pos.map(scalar),
+            vel: self.vel.map(scalar),
+            radius: scalar(self.radius),
+        }
+    }
+    pub fn accel_from_spring_to(&self, other: &Self, spring: &Spring<S>) -> Vector3<S> {
         let rel_pos = self.pos - other.pos;
         let radial_distance = rel_pos.magnitude();
-        let inverse_radial_distance = 1.0 / radial_distance;
+        let inverse_radial_distance = S::one() / radial_distance;
         let radial_vel = (self.vel - other.vel).dot(rel_pos) * inverse_radial_distance;
         let radial_force =
             spring.stiffness * (spring.rest_length - radial_distance) - spring.damping * radial_vel;
 
-        (radial_force * inverse_radial_distance) * rel_pos
+        rel_pos * (radial_force * inverse_radial_distance)
     }
-    pub fn accel_from_collision_with(&self, other: &Particle) -> Vector3<f32> {
+    pub fn accel_from_collision_with(&self, other: &Self) -> Vector3<S> {
         let rel_pos = self.pos - other.pos;
         let penetration = self.radius + other.radius - rel_pos.magnitude();
-        if penetration < 0.0 {
+        if penetration < S::zero() {
             Vector3::zero()
         } else {
-            const STIFFNESS: f32 = 10000.0;
-            const DAMPING: f32 = 1.0;
+            const STIFFNESS: f64 = 10000.0;
+            const DAMPING: f64 = 1.0;
             let rel_normal_vel = (self.vel - other.vel).dot(rel_pos.normalize());
-            rel_pos * (penetration * STIFFNESS - rel_normal_vel * DAMPING)
+            rel_pos * (penetration * scalar(STIFFNESS) - rel_normal_vel * scalar(DAMPING))
         }
     }
+    /// Like [`Particle::accel_from_collision_with`], but with friction and
+    /// restitution as given by `contact`.
+    pub fn accel_from_contact_with(&self, other: &Self, contact: &PenaltyContact<S>) -> Vector3<S> {
+        let rel_pos = self.pos - other.pos;
+        let distance = rel_pos.magnitude();
+        if distance == S::zero() {
+            return Vector3::zero();
+        }
+        contact.accel_with_mass(
+            distance - self.radius - other.radius,
+            rel_pos / distance,
+            self.vel - other.vel,
+            scalar(0.5),
+        )
+    }
 }
-impl Default for Particle {
+impl<S: BaseFloat> Default for Particle<S> {
     fn default() -> Self {
         Self {
             pos: Vector3::zero(),
             vel: Vector3::zero(),
-            radius: 0.0,
+            radius: S::zero(),
         }
     }
 }
 
-pub struct Spring {
-    pub stiffness: f32,
-    pub damping: f32,
-    pub rest_length: f32,
+#[derive(Clone, Copy, Debug)]
+pub struct Spring<S = f32> {
+    pub stiffness: S,
+    pub damping: S,
+    pub rest_length: S,
 }
 
 impl Spring {
@@ -61,64 +124,53 @@ impl Spring {
     };
 }
 
-pub struct Plane {
-    normal: Vector3<f32>,
-    offset: f32,
-    stiffness: f32,
-    normal_damping: f32,
-    tangential_damping: f32,
-}
-
-impl Plane {
-    pub const FLOOR: &'static Self = &Self {
-        normal: Vector3::new(0.0, 0.0, 1.0),
-        offset: 0.0,
-        stiffness: 5000.0,
-        normal_damping: 5.0,
-        tangential_damping: 3.0,
-    };
-    pub fn collide_with(&self, particle: &Particle) -> Vector3<f32> {
-        let pos = particle.pos.dot(self.normal) - self.offset - particle.radius;
-        if pos > 0.0 {
-            Vector3::zero()
-        } else {
-            let normal_vel = particle.vel.dot(self.normal);
-            let tangent_vel = particle.vel - self.normal * normal_vel;
-            let normal_force = -self.stiffness * pos - normal_vel * self.normal_damping;
-            let tangent_force = -self.tangential_damping * tangent_vel;
-            self.normal * normal_force + tangent_force
+impl<S: BaseFloat> Spring<S> {
+    pub fn cast<T: BaseFloat>(&self) -> Spring<T> {
+        Spring {
+            stiffness: scalar(self.stiffness),
+            damping: scalar(self.damping),
+            rest_length: scalar(self.rest_length),
         }
     }
 }
 
-pub fn time_step_with_rk4<T>(
-    particles: &[Particle],
+/// Advance `particles` by [`DT`] under `accelerations`, with the classic
+/// fourth order Runge-Kutta method.
+///
+/// # Panics
+///
+/// If `accelerations` does not return one acceleration per particle.
+pub fn time_step_with_rk4<S: BaseFloat, T>(
+    particles: &[Particle<S>],
     extra_state: &T,
-    accelerations: impl Fn(&[Particle], &T) -> Vec<Vector3<f32>>,
-) -> Vec<Particle> {
-    let mut new_particles = vec![Particle::default(); particles.len()];
+    accelerations: impl Fn(&[Particle<S>], &T) -> Vec<Vector3<S>>,
+) -> Vec<Particle<S>> {
+    let dt: S = scalar(DT);
+    let half_dt: S = scalar(DT / 2.0);
+    // Every stage starts as a copy, so that contacts see the real radii
+    let mut new_particles = particles.to_vec();
 
     let a0s = accelerations(particles, extra_state);
     assert_eq!(particles.len(), a0s.len());
     for ((new, old), a0) in new_particles.iter_mut().zip(particles).zip(a0s.iter()) {
-        new.pos = old.pos + old.vel * (DT / 2.0);
-        new.vel = old.vel + a0 * (DT / 2.0);
+        new.pos = old.pos + old.vel * half_dt;
+        new.vel = old.vel + a0 * half_dt;
     }
 
     let a1s = accelerations(&new_particles, extra_state);
     assert_eq!(particles.len(), a1s.len());
     for ((new, old), a1) in new_particles.iter_mut().zip(particles).zip(a1s.iter()) {
         // Data dependency (current) or recompute?
-        new.pos = old.pos + new.vel * (DT / 2.0);
-        new.vel = old.vel + a1 * (DT / 2.0);
+        new.pos = old.pos + new.vel * half_dt;
+        new.vel = old.vel + a1 * half_dt;
     }
 
     let a2s = accelerations(&new_particles, extra_state);
     assert_eq!(particles.len(), a2s.len());
     for ((new, old), a2) in new_particles.iter_mut().zip(particles).zip(&a2s) {
         // Data dependency (current) or recompute?
-        new.pos = old.pos + new.vel * DT;
-        new.vel = old.vel + a2 * DT;
+        new.pos = old.pos + new.vel * dt;
+        new.vel = old.vel + a2 * dt;
     }
     let a3s = accelerations(&new_particles, extra_state);
     assert_eq!(particles.len(), a3s.len());
@@ -130,9 +182,108 @@ pub fn time_step_with_rk4<T>(
     {
         let a012 = a0 + a1 + a2;
         let a123 = a1 + a2 + a3;
-        new.pos = old.pos + old.vel * DT + a012 * (DT * DT / 4.0);
-        new.vel = old.vel + (a012 + a123) * (DT / 6.0);
-        new.radius = old.radius;
+        new.pos = old.pos + old.vel * dt + a012 * scalar(DT * DT / 6.0);
+        new.vel = old.vel + (a012 + a123) * scalar(DT / 6.0);
     }
     new_particles
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCEL: Vector3<f64> = Vector3::new(0.5, -2.0, -9.81);
+    const STEPS: u32 = 100;
+
+    fn start() -> Particle<f64> {
+        Particle::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            Vector3::new(-1.0, 0.5, 4.0),
+            0.1,
+        )
+    }
+
+    // Exact for a constant acceleration: x0 + v0 t + a t^2 / 2
+    fn assert_exact(particle: &Particle<f64>) {
+        let time = f64::from(STEPS) * DT;
+        let expected_pos = start().pos + start().vel * time + ACCEL * (time * time / 2.0);
+        let expected_vel = start().vel + ACCEL * time;
+        assert!((particle.pos - expected_pos).magnitude() < 1e-12);
+        assert!((particle.vel - expected_vel).magnitude() < 1e-12);
+    }
+
+    #[test]
+    fn rk4_integrates_constant_acceleration_exactly() {
+        let mut particles = vec![start()];
+        for _ in 0..STEPS {
+            particles =
+                time_step_with_rk4(&particles, &(), |particles, _| vec![ACCEL; particles.len()]);
+        }
+        assert_exact(&particles[0]);
+    }
+
+    #[test]
+    fn particle_set_rk4_integrates_constant_acceleration_exactly() {
+        let mut particles = ParticleSet::from_particles(&[start()]);
+        let mut scratch = Rk4Scratch::new();
+        for _ in 0..STEPS {
+            step_with_rk4(&mut particles, &mut scratch, &(), |_, _, accels| {
+                accels.fill(ACCEL);
+            });
+        }
+        assert_exact(&particles.get(0));
+    }
+
+    #[test]
+    fn rk4_is_fourth_order_on_a_harmonic_oscillator() {
+        // x'' = -x from x = 1 at rest, so x = cos(t)
+        let mut particles = vec![Particle::new(Vector3::unit_x(), Vector3::zero(), 0.1)];
+        let steps = 1000;
+        for _ in 0..steps {
+            particles = time_step_with_rk4(&particles, &(), |particles, _| {
+                particles.iter().map(|p| -p.pos).collect()
+            });
+        }
+        let time = f64::from(steps) * DT;
+        assert!((particles[0].pos.x - time.cos()).abs() < 1e-9);
+        assert!((particles[0].vel.x + time.sin()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn both_rk4_paths_agree_on_contacts() {
+        // Two overlapping balls falling onto a floor they already touch
+        let start = [
+            Particle::new(Vector3::new(0.0, 0.0, 0.08), Vector3::zero(), 0.1),
+            Particle::new(Vector3::new(0.15, 0.0, 0.12), Vector3::unit_y(), 0.1),
+        ];
+        let floor = Plane::through(Vector3::zero(), Vector3::unit_z());
+        let accel = |particles: &[Particle<f64>], i: usize| {
+            let others: Vector3<f64> = (0..particles.len())
+                .filter(|&j| j != i)
+                .map(|j| particles[i].accel_from_collision_with(&particles[j]))
+                .sum();
+            others + floor.collide_with(&particles[i]) + ACCEL
+        };
+
+        let mut vec = start.to_vec();
+        let mut set = ParticleSet::from_particles(&start);
+        let mut scratch = Rk4Scratch::new();
+        for _ in 0..STEPS {
+            vec = time_step_with_rk4(&vec, &(), |particles, _| {
+                (0..particles.len()).map(|i| accel(particles, i)).collect()
+            });
+            step_with_rk4(&mut set, &mut scratch, &(), |view, _, accels| {
+                let particles: Vec<_> = view.iter().collect();
+                for (i, out) in accels.iter_mut().enumerate() {
+                    *out = accel(&particles, i);
+                }
+            });
+        }
+        for (i, particle) in vec.iter().enumerate() {
+            let other = set.get(i);
+            assert!((particle.pos - other.pos).magnitude() < 1e-12);
+            assert!((particle.vel - other.vel).magnitude() < 1e-12);
+            assert_eq!(particle.radius.to_bits(), other.radius.to_bits());
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use super::{scalar, Particle, DT};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// Particles stored as a structure of arrays, for stepping many of them with
+/// [`step_with_rk4`] without allocating.
+#[derive(Clone, Debug, Default)]
+pub struct ParticleSet<S = f32> {
+    pub pos: Vec<Vector3<S>>,
+    pub vel: Vec<Vector3<S>>,
+    pub radius: Vec<S>,
+}
+
+impl<S: BaseFloat> ParticleSet<S> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pos: Vec::new(),
+            vel: Vec::new(),
+            radius: Vec::new(),
+        }
+    }
+    pub fn from_particles(particles: &[Particle<S>]) -> Self {
+        let mut set = Self::new();
+        set.extend(particles.iter().copied());
+        set
+    }
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pos.len()
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pos.is_empty()
+    }
+    pub fn push(&mut self, particle: Particle<S>) {
+        self.pos.push(particle.pos);
+        self.vel.push(particle.vel);
+        self.radius.push(particle.radius);
+    }
+    /// Remove every particle, keeping the allocations.
+    pub fn clear(&mut self) {
+        self.pos.clear();
+        self.vel.clear();
+        self.radius.clear();
+    }
+    #[must_use]
+    pub fn get(&self, index: usize) -> Particle<S> {
+        self.view().get(index)
+    }
+    pub fn set(&mut self, index: usize, particle: Particle<S>) {
+        self.pos[index] = particle.pos;
+        self.vel[index] = particle.vel;
+        self.radius[index] = particle.radius;
+    }
+    pub fn iter(&self) -> impl Iterator<Item = Particle<S>> + '_ {
+        self.view().iter()
+    }
+    #[must_use]
+    pub fn view(&self) -> ParticleView<'_, S> {
+        ParticleView {
+            pos: &self.pos,
+            vel: &self.vel,
+            radius: &self.radius,
+        }
+    }
+}
+
+impl<S: BaseFloat> Extend<Particle<S>> for ParticleSet<S> {
+    fn extend<I: IntoIterator<Item = Particle<S>>>(&mut self, particles: I) {
+        for particle in particles {
+            self.push(particle);
+        }
+    }
+}
+
+/// A borrowed [`ParticleSet`], or a stage of one during integration.
+#[derive(Clone, Copy, Debug)]
+pub struct ParticleView<'a, S = f32> {
+    pub pos: &'a [Vector3<S>],
+    pub vel: &'a [Vector3<S>],
+    pub radius: &'a [S],
+}
+
+impl<'a, S: BaseFloat> ParticleView<'a, S> {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pos.len()
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pos.is_empty()
+    }
+    #[must_use]
+    pub fn get(&self, index: usize) -> Particle<S> {
+        Particle::new(self.pos[index], self.vel[index], self.radius[index])
+    }
+    pub fn iter(&self) -> impl Iterator<Item = Particle<S>> + 'a {
+        let (pos, vel, radius) = (self.pos, self.vel, self.radius);
+        pos.iter()
+            .zip(vel)
+            .zip(radius)
+            .map(|((&pos, &vel), &radius)| Particle::new(pos, vel, radius))
+    }
+}
+
+/// The intermediate state of [`step_with_rk4`], kept between calls so that
+/// stepping does not allocate once the buffers have grown.
+#[derive(Clone, Debug, Default)]
+pub struct Rk4Scratch<S = f32> {
+    stage_pos: Vec<Vector3<S>>,
+    stage_vel: Vec<Vector3<S>>,
+    accels: [Vec<Vector3<S>>; 4],
+}
+
+impl<S: BaseFloat> Rk4Scratch<S> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            stage_pos: Vec::new(),
+            stage_vel: Vec::new(),
+            accels: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+        }
+    }
+    fn resize(&mut self, len: usize) {
+        self.stage_pos.resize(len, Vector3::zero());
+        self.stage_vel.resize(len, Vector3::zero());
+        for accels in &mut self.accels {
+            accels.resize(len, Vector3::zero());
+        }
+    }
+}
+
+/// Like [`super::time_step_with_rk4`], but stepping `particles` in place and
+/// keeping all intermediate state in `scratch`.
+///
+/// `accelerations` must fill its output slice with the acceleration of every
+/// particle in the view. Use [`par_accelerations`] to spread that over
+/// threads.
+pub fn step_with_rk4<S: BaseFloat, T>(
+    particles: &mut ParticleSet<S>,
+    scratch: &mut Rk4Scratch<S>,
+    extra_state: &T,
+    accelerations: impl Fn(ParticleView<'_, S>, &T, &mut [Vector3<S>]),
+) {
+    let dt: S = scalar(DT);
+    let half_dt: S = scalar(DT / 2.0);
+    scratch.resize(particles.len());
+    let Rk4Scratch {
+        stage_pos,
+        stage_vel,
+        accels: [a0s, a1s, a2s, a3s],
+    } = scratch;
+    let radius = &particles.radius[..];
+
+    accelerations(particles.view(), extra_state, a0s);
+    for (((stage_pos, stage_vel), (pos, vel)), a0) in stage_pos
+        .iter_mut()
+        .zip(stage_vel.iter_mut())
+        .zip(particles.pos.iter().zip(&particles.vel))
+        .zip(a0s.iter())
+    {
+        *stage_pos = pos + vel * half_dt;
+        *stage_vel = vel + a0 * half_dt;
+    }
+
+    accelerations(
+        ParticleView {
+            pos: stage_pos,
+            vel: stage_vel,
+            radius,
+        },
+        extra_state,
+        a1s,
+    );
+    for (((stage_pos, stage_vel), (pos, vel)), a1) in stage_pos
+        .iter_mut()
+        .zip(stage_vel.iter_mut())
+        .zip(particles.pos.iter().zip(&particles.vel))
+        .zip(a1s.iter())
+    {
+        *stage_pos = pos + *stage_vel * half_dt;
+        *stage_vel = vel + a1 * half_dt;
+    }
+
+    accelerations(
+        ParticleView {
+            pos: stage_pos,
+            vel: stage_vel,
+            radius,
+        },
+        extra_state,
+        a2s,
+    );
+    for (((stage_pos, stage_vel), (pos, vel)), a2) in stage_pos
+        .iter_mut()
+        .zip(stage_vel.iter_mut())
+        .zip(particles.pos.iter().zip(&particles.vel))
+        .zip(a2s.iter())
+    {
+        *stage_pos = pos + *stage_vel * dt;
+        *stage_vel = vel + a2 * dt;
+    }
+
+    accelerations(
+        ParticleView {
+            pos: stage_pos,
+            vel: stage_vel,
+            radius,
+        },
+        extra_state,
+        a3s,
+    );
+    let (pos_weight, vel_weight) = (scalar::<S>(DT * DT / 6.0), scalar::<S>(DT / 6.0));
+    for ((pos, vel), (((a0, a1), a2), a3)) in particles
+        .pos
+        .iter_mut()
+        .zip(particles.vel.iter_mut())
+        .zip(a0s.iter().zip(a1s.iter()).zip(a2s.iter()).zip(a3s.iter()))
+    {
+        let a012 = a0 + a1 + a2;
+        let a123 = a1 + a2 + a3;
+        *pos += *vel * dt + a012 * pos_weight;
+        *vel += (a012 + a123) * vel_weight;
+    }
+}
+
+// Below this many particles, threads cost more than they save
+const PARALLEL_THRESHOLD: usize = 2048;
+
+/// Fill `accels` with `accel(particles, i)` for every particle `i`, split
+/// across threads when there are many particles.
+///
+/// # Panics
+///
+/// If `accels` is not as long as `particles`.
+pub fn par_accelerations<S: BaseFloat + Send + Sync>(
+    particles: ParticleView<'_, S>,
+    accels: &mut [Vector3<S>],
+    accel: impl Fn(ParticleView<'_, S>, usize) -> Vector3<S> + Sync,
+) {
+    assert_eq!(particles.len(), accels.len());
+    if particles.len() < PARALLEL_THRESHOLD || thread_count() == 1 {
+        for (i, out) in accels.iter_mut().enumerate() {
+            *out = accel(particles, i);
+        }
+        return;
+    }
+
+    let chunk_len = (particles.len() + thread_count() - 1) / thread_count();
+    let accel = &accel;
+    thread::scope(|scope| {
+        for (chunk, outs) in accels.chunks_mut(chunk_len).enumerate() {
+            scope.spawn(move || {
+                for (offset, out) in outs.iter_mut().enumerate() {
+                    *out = accel(particles, chunk * chunk_len + offset);
+                }
+            });
+        }
+    });
+}
+
+// Querying the parallelism may read system files, so only do it once. Threads
+// racing to do it first merely query twice.
+fn thread_count() -> usize {
+    static THREAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+    match THREAD_COUNT.load(Ordering::Relaxed) {
+        0 => {
+            let count = thread::available_parallelism().map_or(1, usize::from);
+            THREAD_COUNT.store(count, Ordering::Relaxed);
+            count
+        }
+        count => count,
+    }
+}
@@ -0,0 +1,186 @@
+use super::{scalar, DT};
+use cgmath::{prelude::*, BaseFloat, Quaternion, Vector3};
+use std::ops::{Add, AddAssign};
+
+/// A body that rotates as well as moves, described about its center of mass.
+///
+/// Angular velocities and torques are in world coordinates, as for
+/// [`crate::sensors::Imu::measure`]. The inertia is given along the body axes,
+/// which must be its principal axes.
+#[derive(Clone, Copy, Debug)]
+pub struct RigidBody<S = f32> {
+    pub pos: Vector3<S>,
+    pub vel: Vector3<S>,
+    /// Rotates body to world coordinates.
+    pub orientation: Quaternion<S>,
+    pub angular_vel: Vector3<S>,
+    pub mass: S,
+    /// The principal moments of inertia.
+    pub inertia: Vector3<S>,
+}
+
+/// A force and a torque about the center of mass of a [`RigidBody`], in world
+/// coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct Wrench<S = f32> {
+    pub force: Vector3<S>,
+    pub torque: Vector3<S>,
+}
+
+impl<S: BaseFloat> Wrench<S> {
+    #[must_use]
+    pub fn zero() -> Self {
+        Self {
+            force: Vector3::zero(),
+            torque: Vector3::zero(),
+        }
+    }
+    /// A pure force through the center of mass.
+    pub fn force(force: Vector3<S>) -> Self {
+        Self {
+            force,
+            torque: Vector3::zero(),
+        }
+    }
+    /// A pure torque.
+    pub fn torque(torque: Vector3<S>) -> Self {
+        Self {
+            force: Vector3::zero(),
+            torque,
+        }
+    }
+    /// `force` applied at `offset` from the center of mass.
+    pub fn at_offset(force: Vector3<S>, offset: Vector3<S>) -> Self {
+        Self {
+            force,
+            torque: offset.cross(force),
+        }
+    }
+}
+
+impl<S: BaseFloat> Add for Wrench<S> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self {
+            force: self.force + other.force,
+            torque: self.torque + other.torque,
+        }
+    }
+}
+
+impl<S: BaseFloat> AddAssign for Wrench<S> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<S: BaseFloat> std::iter::Sum for Wrench<S> {
+    fn sum<I: Iterator<Item = Self>>(wrenches: I) -> Self {
+        wrenches.fold(Self::zero(), Add::add)
+    }
+}
+
+// The rate of change of a rigid body's state
+#[derive(Clone, Copy)]
+struct Derivative<S> {
+    vel: Vector3<S>,
+    accel: Vector3<S>,
+    orientation: Quaternion<S>,
+    angular_accel: Vector3<S>,
+}
+
+impl<S: BaseFloat> RigidBody<S> {
+    /// A body at rest at `pos`, with its axes along the world axes.
+    pub fn new(pos: Vector3<S>, mass: S, inertia: Vector3<S>) -> Self {
+        Self {
+            pos,
+            vel: Vector3::zero(),
+            orientation: Quaternion::one(),
+            angular_vel: Vector3::zero(),
+            mass,
+            inertia,
+        }
+    }
+
+    /// Where `point`, in body coordinates, is in the world.
+    pub fn to_world(&self, point: Vector3<S>) -> Vector3<S> {
+        self.pos + self.orientation * point
+    }
+    /// The velocity of the material point at world position `point`.
+    pub fn velocity_at(&self, point: Vector3<S>) -> Vector3<S> {
+        self.vel + self.angular_vel.cross(point - self.pos)
+    }
+
+    /// The linear and angular accelerations under `wrench`, including the
+    /// gyroscopic effect of the body's own spin.
+    pub fn accels(&self, wrench: &Wrench<S>) -> (Vector3<S>, Vector3<S>) {
+        let to_body = self.orientation.conjugate();
+        let angular_vel = to_body * self.angular_vel;
+        let torque = to_body * wrench.torque;
+        let momentum = self.inertia.mul_element_wise(angular_vel);
+        let angular_accel = (torque - angular_vel.cross(momentum)).div_element_wise(self.inertia);
+        (wrench.force / self.mass, self.orientation * angular_accel)
+    }
+
+    fn derivative(&self, wrench: &Wrench<S>) -> Derivative<S> {
+        let (accel, angular_accel) = self.accels(wrench);
+        let half: S = scalar(0.5);
+        Derivative {
+            vel: self.vel,
+            accel,
+            orientation: Quaternion::from_sv(S::zero(), self.angular_vel) * self.orientation * half,
+            angular_accel,
+        }
+    }
+    fn offset(&self, derivative: &Derivative<S>, dt: S) -> Self {
+        Self {
+            pos: self.pos + derivative.vel * dt,
+            vel: self.vel + derivative.accel * dt,
+            orientation: self.orientation + derivative.orientation * dt,
+            angular_vel: self.angular_vel + derivative.angular_accel * dt,
+            ..*self
+        }
+    }
+
+    /// Advance by one time step with RK4, where `wrench` gives the wrench on
+    /// the body in any intermediate state.
+    #[must_use]
+    pub fn step_with_rk4(&self, wrench: impl Fn(&Self) -> Wrench<S>) -> Self {
+        let dt: S = scalar(DT);
+        let half_dt: S = scalar(DT / 2.0);
+
+        let d0 = self.derivative(&wrench(self));
+        let stage = self.offset(&d0, half_dt);
+        let d1 = stage.derivative(&wrench(&stage));
+        let stage = self.offset(&d1, half_dt);
+        let d2 = stage.derivative(&wrench(&stage));
+        let stage = self.offset(&d2, dt);
+        let d3 = stage.derivative(&wrench(&stage));
+
+        let two: S = scalar(2.0);
+        let sixth: S = scalar(1.0 / 6.0);
+        let mean = Derivative {
+            vel: (d0.vel + (d1.vel + d2.vel) * two + d3.vel) * sixth,
+            accel: (d0.accel + (d1.accel + d2.accel) * two + d3.accel) * sixth,
+            orientation: (d0.orientation
+                + (d1.orientation + d2.orientation) * two
+                + d3.orientation)
+                * sixth,
+            angular_accel: (d0.angular_accel
+                + (d1.angular_accel + d2.angular_accel) * two
+                + d3.angular_accel)
+                * sixth,
+        };
+        let mut body = self.offset(&mean, dt);
+        body.orientation = body.orientation.normalize();
+        body
+    }
+
+    /// Kinetic energy, of both motion and rotation.
+    pub fn kinetic_energy(&self) -> S {
+        let angular_vel = self.orientation.conjugate() * self.angular_vel;
+        let half: S = scalar(0.5);
+        half * (self.mass * self.vel.magnitude2()
+            + self.inertia.mul_element_wise(angular_vel).dot(angular_vel))
+    }
+}
@@ -0,0 +1,173 @@
+use super::{scalar, CollisionWorld, Contact, Particle};
+use cgmath::{prelude::*, BaseFloat, Vector3};
+use std::f64::consts::PI;
+
+/// Smoothed-particle hydrodynamics: a weakly compressible fluid made of
+/// (unit mass) particles, each smearing its mass over the smoothing length.
+///
+/// Neighbours are found with a [`CollisionWorld`], so fluid particles must
+/// have [`SphFluid::particle_radius`], half the smoothing length. Container
+/// walls are ordinary [`super::Collider`]s acting on the same particles.
+///
+/// The kernels are those of Müller et al. 2003, "Particle-Based Fluid
+/// Simulation for Interactive Applications".
+#[derive(Clone, Copy, Debug)]
+pub struct SphFluid<S = f32> {
+    /// The distance over which particles interact.
+    pub smoothing_length: S,
+    /// The density at which the pressure is zero.
+    pub rest_density: S,
+    /// Pressure per unit of density above the rest density. Its square root is
+    /// the speed of sound, which must stay well below the smoothing length per
+    /// time step.
+    pub stiffness: S,
+    pub viscosity: S,
+}
+
+impl<S: BaseFloat> SphFluid<S> {
+    /// A fluid meant to be sampled by particles `spacing` apart, at rest
+    /// exactly when they are on a cubic lattice.
+    pub fn new(spacing: S, stiffness: S, viscosity: S) -> Self {
+        let mut fluid = Self {
+            smoothing_length: spacing * scalar(2.0),
+            rest_density: S::one(),
+            stiffness,
+            viscosity,
+        };
+        // The density in the middle of a lattice
+        let mut rest_density = S::zero();
+        for x in -3..=3 {
+            for y in -3..=3 {
+                for z in -3..=3 {
+                    let offset = Vector3::new(f64::from(x), f64::from(y), f64::from(z)).map(scalar)
+                        * spacing;
+                    rest_density += fluid.density_kernel(offset.magnitude2());
+                }
+            }
+        }
+        fluid.rest_density = rest_density;
+        fluid
+    }
+
+    /// The radius fluid particles must have for neighbour search.
+    pub fn particle_radius(&self) -> S {
+        self.smoothing_length / scalar(2.0)
+    }
+
+    // Poly6, as a function of the squared distance
+    fn density_kernel(&self, distance2: S) -> S {
+        let h = self.smoothing_length;
+        let h2 = h * h;
+        if distance2 >= h2 {
+            return S::zero();
+        }
+        let diff = h2 - distance2;
+        scalar::<S>(315.0 / (64.0 * PI)) * diff * diff * diff / h2.powi(4) / h
+    }
+    // The magnitude of the gradient of the spiky kernel
+    fn pressure_kernel_gradient(&self, distance: S) -> S {
+        let h = self.smoothing_length;
+        let diff = h - distance;
+        scalar::<S>(-45.0 / PI) * diff * diff / h.powi(6)
+    }
+    // The Laplacian of the viscosity kernel
+    fn viscosity_kernel_laplacian(&self, distance: S) -> S {
+        let h = self.smoothing_length;
+        scalar::<S>(45.0 / PI) * (h - distance) / h.powi(6)
+    }
+
+    /// The density at every particle. `neighbours` must have been built from
+    /// `particles`.
+    pub fn densities(&self, particles: &[Particle<S>], neighbours: &CollisionWorld) -> Vec<S> {
+        let own = self.density_kernel(S::zero());
+        let mut densities = vec![own; particles.len()];
+        neighbours.for_each_contact(particles, |contact| {
+            let distance = self.distance(&contact);
+            let density = self.density_kernel(distance * distance);
+            densities[contact.first] += density;
+            densities[contact.second] += density;
+        });
+        densities
+    }
+
+    /// The pressure and viscosity accelerations of every particle.
+    pub fn accels(&self, particles: &[Particle<S>]) -> Vec<Vector3<S>> {
+        let neighbours = CollisionWorld::new(particles);
+        let densities = self.densities(particles, &neighbours);
+        // Clamping at zero keeps free surfaces from clumping
+        let pressures: Vec<S> = densities
+            .iter()
+            .map(|&density| (self.stiffness * (density - self.rest_density)).max(S::zero()))
+            .collect();
+
+        let mut accels = vec![Vector3::zero(); particles.len()];
+        neighbours.for_each_contact(particles, |contact| {
+            let Contact {
+                first: i,
+                second: j,
+                normal,
+                ..
+            } = contact;
+            let distance = self.distance(&contact);
+            // Symmetric in i and j, so momentum is conserved
+            let pressure = normal
+                * (-(pressures[i] + pressures[j]) / scalar(2.0)
+                    * self.pressure_kernel_gradient(distance));
+            let viscosity = (particles[j].vel - particles[i].vel)
+                * (self.viscosity * self.viscosity_kernel_laplacian(distance));
+            let accel = (pressure + viscosity) / (densities[i] * densities[j]);
+            accels[i] += accel;
+            accels[j] -= accel;
+        });
+        accels
+    }
+
+    fn distance(&self, contact: &Contact<S>) -> S {
+        self.smoothing_length - contact.penetration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{dt, time_step_with_rk4};
+
+    #[test]
+    fn pressure_pushes_a_compressed_block_apart() {
+        let fluid = SphFluid::new(0.1, 40.0, 0.0);
+        let radius = fluid.particle_radius();
+        // A block squeezed to 80% of the rest spacing
+        let mut particles = Vec::new();
+        for x in -2..=2 {
+            for y in -2..=2 {
+                for z in -2..=2 {
+                    let pos = Vector3::new(f64::from(x), f64::from(y), f64::from(z)) * 0.08;
+                    particles.push(Particle::new(pos, Vector3::zero(), radius));
+                }
+            }
+        }
+        let neighbours = CollisionWorld::new(&particles);
+        let middle = fluid.densities(&particles, &neighbours)[particles.len() / 2];
+        assert!(middle > fluid.rest_density);
+
+        // The pressure eases only a little over one step, so the velocity
+        // gained is close to a full step of the initial acceleration, as long
+        // as every RK4 stage finds the neighbours
+        let accels = fluid.accels(&particles);
+        let stepped = time_step_with_rk4(&particles, &(), |particles, _| fluid.accels(particles));
+        let (mut gained, mut expected) = (0.0, 0.0);
+        for ((before, after), accel) in particles.iter().zip(&stepped).zip(&accels) {
+            if before.pos.is_zero() {
+                continue;
+            }
+            assert!(accel.dot(before.pos) > 0.0, "pulled in at {:?}", before.pos);
+            let outward = before.pos.normalize();
+            gained += after.vel.dot(outward);
+            expected += accel.dot(outward) * dt::<f64>();
+        }
+        assert!(
+            (gained / expected - 1.0).abs() < 0.2,
+            "gained {gained}, expected {expected}"
+        );
+    }
+}
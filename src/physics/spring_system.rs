@@ -0,0 +1,299 @@
+use super::{
+    time_step_with_rk4, Collider, CollisionWorld, ContactEvent, ForceField, Gravity, Joint, Motor,
+    Particle, PenaltyContact, Spring,
+};
+use crate::Solid;
+use cgmath::{prelude::*, BaseFloat, Vector3};
+
+/// A declarative mass-spring model: particles, springs and joints between
+/// them, static colliders and force fields.
+///
+/// ```
+/// use agentbox::physics::{Particle, Plane, Spring, SpringSystem};
+/// use cgmath::{prelude::*, Vector3};
+///
+/// let system = SpringSystem::new()
+///     .particle(Particle::new(Vector3::unit_z(), Vector3::zero(), 0.1))
+///     .particle(Particle::new(Vector3::unit_z() * 2.0, Vector3::zero(), 0.1))
+///     .spring(0, 1, *Spring::UNIT_ROD)
+///     .collider(Plane::new(Vector3::unit_z(), 0.0))
+///     .gravity(Vector3::new(0.0, 0.0, -1.0));
+/// let particles = system.step(&system.initial_particles(), 0.0);
+/// let solids = system.solids(&particles);
+/// ```
+pub struct SpringSystem<S: BaseFloat = f32> {
+    particles: Vec<Particle<S>>,
+    pinned: Vec<usize>,
+    springs: Vec<(usize, usize, Spring<S>)>,
+    // Whether each spring is drawn by `solids`
+    spring_visible: Vec<bool>,
+    joints: Vec<Box<dyn Joint<S> + Send + Sync>>,
+    colliders: Vec<Box<dyn Collider<S> + Send + Sync>>,
+    fields: Vec<Box<dyn ForceField<S> + Send + Sync>>,
+    particle_contact: Option<PenaltyContact<S>>,
+    style: SpringSystemStyle,
+}
+
+/// How [`SpringSystem::solids`] draws things.
+#[derive(Clone, Copy, Debug)]
+pub struct SpringSystemStyle {
+    pub particle_color: Vector3<f32>,
+    pub spring_color: Vector3<f32>,
+    pub spring_radius: f32,
+    pub collider_color: Vector3<f32>,
+}
+
+impl SpringSystemStyle {
+    pub const DEFAULT: Self = Self {
+        particle_color: Vector3::new(0.5, 0.2, 0.3),
+        spring_color: Vector3::new(0.0, 0.3, 0.6),
+        spring_radius: 0.05,
+        collider_color: Vector3::new(0.9, 0.9, 0.9),
+    };
+}
+
+impl<S: BaseFloat> SpringSystem<S> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::new(),
+            pinned: Vec::new(),
+            springs: Vec::new(),
+            spring_visible: Vec::new(),
+            joints: Vec::new(),
+            colliders: Vec::new(),
+            fields: Vec::new(),
+            particle_contact: None,
+            style: SpringSystemStyle::DEFAULT,
+        }
+    }
+
+    /// Add a particle. Particles are indexed in the order they are added.
+    #[must_use]
+    pub fn particle(mut self, particle: Particle<S>) -> Self {
+        self.particles.push(particle);
+        self
+    }
+    /// Let no forces act on particle `index`, so that it stays put if it was
+    /// declared at rest.
+    ///
+    /// # Panics
+    ///
+    /// If particle `index` has not been added.
+    #[must_use]
+    pub fn pin(mut self, index: usize) -> Self {
+        assert!(
+            index < self.particles.len(),
+            "pinning nonexistent particle {index}",
+        );
+        self.pinned.push(index);
+        self
+    }
+    /// Connect particles `first` and `second` with `spring`.
+    ///
+    /// # Panics
+    ///
+    /// If either particle has not been added.
+    #[must_use]
+    pub fn spring(self, first: usize, second: usize, spring: Spring<S>) -> Self {
+        self.with_spring(first, second, spring, true)
+    }
+    /// Like [`SpringSystem::spring`], but not drawn by
+    /// [`SpringSystem::solids`], as suits springs that stiffen a structure
+    /// rather than stand for a rod.
+    ///
+    /// # Panics
+    ///
+    /// If either particle has not been added.
+    #[must_use]
+    pub fn hidden_spring(self, first: usize, second: usize, spring: Spring<S>) -> Self {
+        self.with_spring(first, second, spring, false)
+    }
+    fn with_spring(
+        mut self,
+        first: usize,
+        second: usize,
+        spring: Spring<S>,
+        visible: bool,
+    ) -> Self {
+        assert!(
+            first < self.particles.len() && second < self.particles.len(),
+            "spring between nonexistent particles {first} and {second}",
+        );
+        self.springs.push((first, second, spring));
+        self.spring_visible.push(visible);
+        self
+    }
+    /// Add a joint. Joints are indexed in the order they are added.
+    #[must_use]
+    pub fn joint(mut self, joint: impl Joint<S> + Send + Sync + 'static) -> Self {
+        self.joints.push(Box::new(joint));
+        self
+    }
+    #[must_use]
+    pub fn collider(mut self, collider: impl Collider<S> + Send + Sync + 'static) -> Self {
+        self.colliders.push(Box::new(collider));
+        self
+    }
+    /// Let particles collide with each other.
+    #[must_use]
+    pub fn particle_collisions(mut self, contact: PenaltyContact<S>) -> Self {
+        self.particle_contact = Some(contact);
+        self
+    }
+    /// Add a uniform acceleration acting on every particle.
+    #[must_use]
+    pub fn gravity(self, accel: Vector3<S>) -> Self
+    where
+        S: Send + Sync + 'static,
+    {
+        self.field(Gravity { accel })
+    }
+    /// Add a force field acting on every particle.
+    #[must_use]
+    pub fn field(mut self, field: impl ForceField<S> + Send + Sync + 'static) -> Self {
+        self.fields.push(Box::new(field));
+        self
+    }
+    #[must_use]
+    pub fn style(self, style: SpringSystemStyle) -> Self {
+        Self { style, ..self }
+    }
+
+    /// Change the motor of joint `joint`, typically in response to signals.
+    pub fn set_motor(&mut self, joint: usize, motor: Option<Motor<S>>) {
+        self.joints[joint].set_motor(motor);
+    }
+    /// The position of every joint, in the order they were added.
+    pub fn joint_positions(&self, particles: &[Particle<S>]) -> Vec<S> {
+        self.joints
+            .iter()
+            .map(|joint| joint.position(particles))
+            .collect()
+    }
+    /// The velocity of every joint, in the order they were added.
+    pub fn joint_velocities(&self, particles: &[Particle<S>]) -> Vec<S> {
+        self.joints
+            .iter()
+            .map(|joint| joint.velocity(particles))
+            .collect()
+    }
+
+    /// The number of particles declared so far, which is also the index the
+    /// next one will get.
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+    /// The particles as they were declared.
+    pub fn initial_particles(&self) -> Vec<Particle<S>> {
+        self.particles.clone()
+    }
+
+    /// The acceleration of every particle at `time`, for use with
+    /// [`time_step_with_rk4`].
+    ///
+    /// # Panics
+    ///
+    /// If `particles` is not as long as the declared particles.
+    pub fn accelerations(&self, particles: &[Particle<S>], time: S) -> Vec<Vector3<S>> {
+        assert_eq!(self.particles.len(), particles.len());
+        let mut accels: Vec<Vector3<S>> = particles
+            .iter()
+            .map(|particle| {
+                let from_colliders = self
+                    .colliders
+                    .iter()
+                    .map(|collider| collider.collide_with(particle));
+                let from_fields = self.fields.iter().map(|field| field.accel(particle, time));
+                from_colliders.chain(from_fields).sum()
+            })
+            .collect();
+
+        for (first, second, spring) in &self.springs {
+            let (first, second) = (*first, *second);
+            accels[first] += particles[first].accel_from_spring_to(&particles[second], spring);
+            accels[second] += particles[second].accel_from_spring_to(&particles[first], spring);
+        }
+
+        for joint in &self.joints {
+            joint.add_accels(particles, &mut accels);
+        }
+
+        if let Some(contact) = &self.particle_contact {
+            let collisions =
+                CollisionWorld::new(particles).accels_from_contacts(particles, contact);
+            for (accel, collision) in accels.iter_mut().zip(collisions) {
+                *accel += collision;
+            }
+        }
+
+        for &index in &self.pinned {
+            accels[index] = Vector3::zero();
+        }
+        accels
+    }
+
+    /// Every contact between particles, and between particles and colliders,
+    /// with colliders indexed in the order they were added.
+    pub fn contact_events(&self, particles: &[Particle<S>]) -> Vec<ContactEvent<S>> {
+        let mut events: Vec<ContactEvent<S>> = self
+            .colliders
+            .iter()
+            .enumerate()
+            .flat_map(|(index, collider)| {
+                particles
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(i, particle)| collider.contact_event(index, particle, i))
+            })
+            .collect();
+        if let Some(contact) = &self.particle_contact {
+            events.extend(CollisionWorld::new(particles).contact_events(particles, contact));
+        }
+        events
+    }
+
+    /// Advance `particles` by one time step, from `time`.
+    pub fn step(&self, particles: &[Particle<S>], time: S) -> Vec<Particle<S>> {
+        time_step_with_rk4(particles, self, |particles, system| {
+            system.accelerations(particles, time)
+        })
+    }
+
+    /// Spheres for particles, cylinders for springs that are not hidden and
+    /// the solids of every collider.
+    pub fn solids(&self, particles: &[Particle<S>]) -> Vec<Solid> {
+        let style = &self.style;
+        let particles: Vec<Particle> = particles.iter().map(Particle::cast).collect();
+        let spheres = particles
+            .iter()
+            .filter(|particle| particle.radius > 0.0)
+            .map(|particle| Solid::new_sphere(particle.pos, particle.radius, style.particle_color));
+        let cylinders = self
+            .springs
+            .iter()
+            .zip(&self.spring_visible)
+            .filter(|(_, &visible)| visible)
+            .map(|(spring, _)| spring)
+            .filter(|(first, second, _)| particles[*first].pos != particles[*second].pos)
+            .map(|(first, second, _)| {
+                Solid::new_cylinder(
+                    particles[*first].pos,
+                    particles[*second].pos,
+                    style.spring_radius,
+                    style.spring_color,
+                )
+            });
+        let colliders = self
+            .colliders
+            .iter()
+            .flat_map(|collider| collider.solids(style.collider_color));
+        spheres.chain(cylinders).chain(colliders).collect()
+    }
+}
+
+impl<S: BaseFloat> Default for SpringSystem<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
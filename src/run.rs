@@ -1,4 +1,4 @@
-use crate::{Model, Status};
+use crate::{physics::diagnostics::DriftMonitor, Model, Status};
 use log::{error, warn};
 use std::{
     panic,
@@ -72,11 +72,22 @@ pub fn run_simulation<M: Model, F>(
     let mut signals = M::new_signals();
     let mut status = initial_status;
     let mut visible = false; // The event loop is initially not visible
+    let mut drift_monitor: Option<DriftMonitor> = None;
 
     loop {
         controller(&world, &mut signals, &mut status);
         M::update(&mut world, &signals);
 
+        // Track energy drift
+        match (status.max_energy_growth, &mut drift_monitor) {
+            (Some(max_growth), None) => drift_monitor = Some(DriftMonitor::new(max_growth)),
+            (None, Some(_)) => drift_monitor = None,
+            _ => {}
+        }
+        if let (Some(monitor), Some(diagnostics)) = (&mut drift_monitor, M::diagnostics(&world)) {
+            monitor.observe(&diagnostics);
+        }
+
         // Tell GUI to quit
         if status.should_quit {
             warn!("Simulation thread exiting.");
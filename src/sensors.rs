@@ -0,0 +1,248 @@
+//! Imperfect measurements, for controllers that should cope with more than
+//! the exact state of a world.
+//!
+//! Every sensor with noise owns a seeded generator, so a run can be repeated
+//! exactly. Sensors are stateful and live with whoever builds the observations,
+//! typically the controller:
+//! ```no_run
+//! use agentbox::{models::BouncingBalls, sensors::PositionSensor, Status};
+//! use cgmath::Vector3;
+//!
+//! let mut gps = PositionSensor::new(0.1, Vector3::new(0.0, 0.0, 0.5), 0.01, 42);
+//! agentbox::run_with::<BouncingBalls, _>(Status::VISUAL, move |world, _signals, _status| {
+//!     let position = gps.measure(world.first.pos, world.time);
+//!     log::info!("the first ball is about {:?}", position);
+//! })
+//! ```
+
+use crate::physics::scalar;
+use cgmath::{prelude::*, BaseFloat, Quaternion, Vector3};
+
+/// A seeded source of standard normal samples.
+#[derive(Clone, Debug)]
+pub struct GaussianNoise {
+    rng: fastrand::Rng,
+    // Box-Muller makes samples in pairs
+    spare: Option<f64>,
+}
+
+impl GaussianNoise {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: fastrand::Rng::with_seed(seed),
+            spare: None,
+        }
+    }
+    /// A sample from the normal distribution with mean 0 and variance 1.
+    pub fn sample(&mut self) -> f64 {
+        if let Some(spare) = self.spare.take() {
+            return spare;
+        }
+        // Keep away from zero, whose logarithm is infinite
+        let radius = (-2.0 * (1.0 - self.rng.f64()).ln()).sqrt();
+        let angle = std::f64::consts::TAU * self.rng.f64();
+        self.spare = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+    /// A vector of independent samples scaled by `std_dev`.
+    pub fn vector<S: BaseFloat>(&mut self, std_dev: S) -> Vector3<S> {
+        Vector3::new(
+            scalar(self.sample()),
+            scalar(self.sample()),
+            scalar(self.sample()),
+        ) * std_dev
+    }
+}
+
+/// Measures a vector quantity with white noise and a bias that drifts as a
+/// random walk.
+#[derive(Clone, Debug)]
+pub struct VectorSensor<S = f32> {
+    /// Standard deviation of the noise in each measurement.
+    pub noise: S,
+    /// How fast the bias drifts: the standard deviation of its change over
+    /// one unit of time.
+    pub drift: S,
+    bias: Vector3<S>,
+    last_time: Option<S>,
+    gaussian: GaussianNoise,
+}
+
+/// Measures positions, like a GPS or motion capture.
+pub type PositionSensor<S = f32> = VectorSensor<S>;
+/// Measures velocities, like a doppler or optical flow sensor.
+pub type VelocitySensor<S = f32> = VectorSensor<S>;
+
+impl<S: BaseFloat> VectorSensor<S> {
+    pub fn new(noise: S, initial_bias: Vector3<S>, drift: S, seed: u64) -> Self {
+        Self {
+            noise,
+            drift,
+            bias: initial_bias,
+            last_time: None,
+            gaussian: GaussianNoise::new(seed),
+        }
+    }
+    /// A sensor without noise, bias or drift.
+    #[must_use]
+    pub fn perfect() -> Self {
+        Self::new(S::zero(), Vector3::zero(), S::zero(), 0)
+    }
+    /// The current bias.
+    pub fn bias(&self) -> Vector3<S> {
+        self.bias
+    }
+    /// Measure `value` at `time`, which must not decrease between calls.
+    pub fn measure(&mut self, value: Vector3<S>, time: S) -> Vector3<S> {
+        let elapsed = self.last_time.map_or(S::zero(), |last| time - last);
+        self.last_time = Some(time);
+        self.bias += self
+            .gaussian
+            .vector(self.drift * elapsed.max(S::zero()).sqrt());
+        value + self.bias + self.gaussian.vector(self.noise)
+    }
+}
+
+/// What an [`Imu`] reports, in the frame of the body it is attached to.
+#[derive(Clone, Copy, Debug)]
+pub struct ImuReading<S = f32> {
+    /// Specific force: the acceleration minus gravity, so an IMU at rest
+    /// reads `-gravity`.
+    pub accel: Vector3<S>,
+    /// Angular velocity.
+    pub gyro: Vector3<S>,
+}
+
+/// An inertial measurement unit: an accelerometer and a gyroscope, each with
+/// the noise and drifting bias of a [`VectorSensor`].
+///
+/// The acceleration is differentiated from the velocities seen in successive
+/// measurements, so an IMU should be fed once per tick.
+#[derive(Clone, Debug)]
+pub struct Imu<S = f32> {
+    pub accelerometer: VectorSensor<S>,
+    pub gyroscope: VectorSensor<S>,
+    last: Option<(S, Vector3<S>)>,
+}
+
+impl<S: BaseFloat> Imu<S> {
+    pub fn new(accelerometer: VectorSensor<S>, gyroscope: VectorSensor<S>) -> Self {
+        Self {
+            accelerometer,
+            gyroscope,
+            last: None,
+        }
+    }
+    /// Measure a body moving at `vel`, with `orientation` rotating body to
+    /// world coordinates, and world-space `angular_vel`.
+    pub fn measure(
+        &mut self,
+        vel: Vector3<S>,
+        orientation: Quaternion<S>,
+        angular_vel: Vector3<S>,
+        gravity: Vector3<S>,
+        time: S,
+    ) -> ImuReading<S> {
+        let accel = match self.last {
+            Some((last_time, last_vel)) if time > last_time => {
+                (vel - last_vel) / (time - last_time)
+            }
+            _ => Vector3::zero(),
+        };
+        self.last = Some((time, vel));
+
+        let to_body = orientation.conjugate();
+        ImuReading {
+            accel: self
+                .accelerometer
+                .measure(to_body * (accel - gravity), time),
+            gyro: self.gyroscope.measure(to_body * angular_vel, time),
+        }
+    }
+    /// Measure a particle, which has no orientation and does not rotate.
+    pub fn measure_particle(
+        &mut self,
+        particle: &crate::physics::Particle<S>,
+        gravity: Vector3<S>,
+        time: S,
+    ) -> ImuReading<S> {
+        self.measure(
+            particle.vel,
+            Quaternion::one(),
+            Vector3::zero(),
+            gravity,
+            time,
+        )
+    }
+}
+
+/// Reads a position quantized to a whole number of counts, like a rotary or
+/// linear encoder.
+#[derive(Clone, Copy, Debug)]
+pub struct Encoder<S = f32> {
+    /// Counts per unit of position, e.g. per radian.
+    pub resolution: S,
+}
+
+impl<S: BaseFloat> Encoder<S> {
+    /// An encoder with `counts` per full turn, for angles in radians.
+    #[must_use]
+    pub fn rotary(counts: u32) -> Self {
+        Self {
+            resolution: scalar(f64::from(counts) / std::f64::consts::TAU),
+        }
+    }
+    /// The whole number of counts at `position`.
+    ///
+    /// # Panics
+    ///
+    /// If the count does not fit in an `i64`.
+    pub fn count(&self, position: S) -> i64 {
+        (position * self.resolution).floor().to_i64().unwrap()
+    }
+    /// `position` rounded down to the nearest count.
+    pub fn read(&self, position: S) -> S {
+        (position * self.resolution).floor() / self.resolution
+    }
+}
+
+/// Holds a measurement, taking a new one only once `period` has passed, to
+/// model sensors running slower than the simulation.
+#[derive(Clone, Debug)]
+pub struct SampleAndHold<T, S = f32> {
+    pub period: S,
+    next_sample: S,
+    held: Option<T>,
+}
+
+impl<T: Clone, S: BaseFloat> SampleAndHold<T, S> {
+    pub fn new(period: S) -> Self {
+        Self {
+            period,
+            next_sample: S::neg_infinity(),
+            held: None,
+        }
+    }
+    /// The held value at `time`, calling `measure` for a new one if due.
+    pub fn sample(&mut self, time: S, measure: impl FnOnce() -> T) -> T {
+        match &self.held {
+            Some(held) if time < self.next_sample => held.clone(),
+            _ => {
+                let value = measure();
+                self.held = Some(value.clone());
+                // Keep a steady rate, but do not try to catch up after a pause
+                self.next_sample = if self.next_sample + self.period > time {
+                    self.next_sample + self.period
+                } else {
+                    time + self.period
+                };
+                value
+            }
+        }
+    }
+    /// The last sample, if any.
+    pub fn held(&self) -> Option<&T> {
+        self.held.as_ref()
+    }
+}
@@ -1,4 +1,4 @@
-use cgmath::{prelude::*, Matrix3, Matrix4, Quaternion, Rad, Vector3, Vector4};
+use cgmath::{prelude::*, Matrix3, Matrix4, Quaternion, Vector3, Vector4};
 
 /// The graphical primitive. A solid can represent any affine transformation of
 /// a sphere, cylinder or cube.
@@ -48,10 +48,7 @@ impl Solid {
         let length_scale = axis.magnitude();
         let world_to_local =
             Matrix4::from_nonuniform_scale(1.0 / radius, 1.0 / radius, 1.0 / length_scale)
-                * Matrix4::from_axis_angle(
-                    axis.cross(Vector3::unit_z()).normalize(),
-                    Rad::acos(Vector3::unit_z().dot(axis / length_scale)),
-                )
+                * Matrix4::from(Quaternion::from_arc(axis, Vector3::unit_z(), None))
                 * Matrix4::from_translation(-midpoint);
         Self::new(world_to_local, color, SolidKind::Cylinder)
     }